@@ -0,0 +1,62 @@
+// Enemy tanks and the decisions they make every tick.
+use crate::{
+    combat::FireIntent,
+    flow::GameState,
+    player::Player,
+    simulation::{Position, SimulationStage, SimulationSystem, TickStage},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.stage(SimulationStage, |schedule: &mut Schedule| {
+            schedule.add_system_set_to_stage(
+                TickStage::Update,
+                SystemSet::on_update(GameState::Playing).with_system(
+                    brown_tank_shoot_system
+                        .label(SimulationSystem::Ai)
+                        .after(SimulationSystem::Input),
+                ),
+            )
+        });
+    }
+}
+
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Component)]
+pub struct BrownTank;
+
+// Type of an enemy tank, telling them apart in statistics
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum EnemyKind {
+    Brown,
+}
+
+impl EnemyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyKind::Brown => "Brown tank",
+        }
+    }
+}
+
+fn brown_tank_shoot_system(
+    brown_tank_query: Query<(Entity, &Position), With<BrownTank>>,
+    player_query: Query<&Position, With<Player>>,
+    mut fire_intents: EventWriter<FireIntent>,
+) {
+    // Brown tanks fire straight at the player whenever their weapon allows it
+    if let Ok(player_position) = player_query.get_single() {
+        for (tank_entity, tank_position) in brown_tank_query.iter() {
+            fire_intents.send(FireIntent {
+                tank: tank_entity,
+                direction: player_position.0 - tank_position.0,
+            });
+        }
+    }
+}
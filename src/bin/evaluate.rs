@@ -0,0 +1,359 @@
+// Runs a bot through every mission of the campaign many times without a window, and prints the
+// win rate, average completion time and causes of death for each mission as CSV or JSON.
+//
+//     cargo run --release --bin evaluate -- --bot sniper --runs 100 --format json
+use bevy::math::Vec2;
+use std::iter;
+use tanks::{
+    env::{Action, Bot, DeathCause, Env, Observation},
+    level::Level,
+    simulation::{SimulationRng, TICK_SECONDS},
+};
+
+const USAGE: &str = "Usage: evaluate [--bot <idle|sniper|random>] [--runs <count>] \
+                     [--seed <seed>] [--max-ticks <count>] [--format <csv|json>]";
+
+// Ticks a mission may last before it is abandoned, three minutes of game time
+const DEFAULT_MAX_TICKS: u64 = 3 * 60 * 60;
+
+// Ticks a bot waits between shots, so it does not empty its magazine at once
+const FIRE_INTERVAL: u64 = 30;
+
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("Error: {}\n{}", error, USAGE);
+        std::process::exit(2)
+    });
+
+    let mut env = Env::new();
+    let mut results = vec![];
+    for level in iter::successors(Some(Level::L1), Level::next) {
+        let mut result = MissionResult::new(level);
+        for run in 0..options.runs {
+            let seed = options.seed + run;
+            let mut bot = create_bot(&options.bot, seed).expect("Error: Unknown bot");
+            result.add(run_mission(
+                &mut env,
+                bot.as_mut(),
+                seed,
+                level,
+                options.max_ticks,
+            ));
+        }
+
+        results.push(result);
+    }
+
+    match options.format {
+        Format::Csv => print_csv(&results),
+        Format::Json => print_json(&options.bot, &results),
+    }
+}
+
+// Options given on the command line
+struct Options {
+    // Bot controlling the player, with `--bot <idle|sniper|random>`
+    bot: String,
+    // Number of runs of every mission, with `--runs <count>`
+    runs: u64,
+    // Seed of the first run, incremented for every following run, with `--seed <seed>`
+    seed: u64,
+    // Ticks after which a mission is abandoned, with `--max-ticks <count>`
+    max_ticks: u64,
+    // Output format, with `--format <csv|json>`
+    format: Format,
+}
+
+impl Options {
+    // Reads the options from the arguments after the program name, failing on any argument or
+    // value it does not understand
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            bot: "sniper".to_string(),
+            runs: 10,
+            seed: 0,
+            max_ticks: DEFAULT_MAX_TICKS,
+            format: Format::Csv,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bot" => {
+                    let bot = value(&mut args, &arg)?;
+                    if create_bot(&bot, 0).is_none() {
+                        return Err(format!("Unknown bot {}", bot));
+                    }
+                    options.bot = bot;
+                }
+                "--runs" => options.runs = parse_value(&mut args, &arg)?,
+                "--seed" => options.seed = parse_value(&mut args, &arg)?,
+                "--max-ticks" => options.max_ticks = parse_value(&mut args, &arg)?,
+                "--format" => {
+                    options.format = match value(&mut args, &arg)?.as_str() {
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        format => return Err(format!("Unknown format {}", format)),
+                    }
+                }
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+// helper function to take the value following an option
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", option))
+}
+
+// helper function to parse the number following an option
+fn parse_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<u64, String> {
+    let value = value(args, option)?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {} for {}", value, option))
+}
+
+enum Format {
+    Csv,
+    Json,
+}
+
+// How a single run of a mission ended
+enum Outcome {
+    Win { ticks: u64 },
+    Loss(Option<DeathCause>),
+    Timeout,
+}
+
+// Totals over every run of a single mission
+struct MissionResult {
+    level: Level,
+    runs: u64,
+    wins: u64,
+    losses: u64,
+    timeouts: u64,
+    // Ticks taken by all winning runs together
+    win_ticks: u64,
+    enemy_bullet_deaths: u64,
+    own_bullet_deaths: u64,
+    unknown_deaths: u64,
+}
+
+impl MissionResult {
+    fn new(level: Level) -> MissionResult {
+        MissionResult {
+            level,
+            runs: 0,
+            wins: 0,
+            losses: 0,
+            timeouts: 0,
+            win_ticks: 0,
+            enemy_bullet_deaths: 0,
+            own_bullet_deaths: 0,
+            unknown_deaths: 0,
+        }
+    }
+
+    fn add(&mut self, outcome: Outcome) {
+        self.runs += 1;
+        match outcome {
+            Outcome::Win { ticks } => {
+                self.wins += 1;
+                self.win_ticks += ticks;
+            }
+            Outcome::Loss(cause) => {
+                self.losses += 1;
+                match cause {
+                    Some(DeathCause::EnemyBullet) => self.enemy_bullet_deaths += 1,
+                    Some(DeathCause::OwnBullet) => self.own_bullet_deaths += 1,
+                    None => self.unknown_deaths += 1,
+                }
+            }
+            Outcome::Timeout => self.timeouts += 1,
+        }
+    }
+
+    fn win_rate(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.runs as f64
+        }
+    }
+
+    // Average game time in seconds the winning runs took, if there were any
+    fn average_completion_seconds(&self) -> Option<f64> {
+        if self.wins == 0 {
+            None
+        } else {
+            Some(self.win_ticks as f64 / self.wins as f64 * TICK_SECONDS)
+        }
+    }
+}
+
+fn run_mission(
+    env: &mut Env,
+    bot: &mut dyn Bot,
+    seed: u64,
+    level: Level,
+    max_ticks: u64,
+) -> Outcome {
+    let mut observation = env.reset(seed, level);
+    let start = observation.tick;
+
+    while observation.tick - start < max_ticks {
+        let (next, _, done) = env.step(bot.act(&observation));
+        observation = next;
+
+        // Winning takes precedence when the player and the last enemy are destroyed together
+        if done {
+            return if observation.enemies.is_empty() {
+                Outcome::Win {
+                    ticks: observation.tick - start,
+                }
+            } else {
+                Outcome::Loss(env.death_cause())
+            };
+        }
+    }
+
+    Outcome::Timeout
+}
+
+fn print_csv(results: &[MissionResult]) {
+    println!(
+        "mission,runs,wins,losses,timeouts,win_rate,average_completion_seconds,\
+         enemy_bullet_deaths,own_bullet_deaths,unknown_deaths"
+    );
+    for result in results {
+        println!(
+            "{},{},{},{},{},{:.3},{},{},{},{}",
+            result.level.id(),
+            result.runs,
+            result.wins,
+            result.losses,
+            result.timeouts,
+            result.win_rate(),
+            result
+                .average_completion_seconds()
+                .map_or(String::new(), |seconds| format!("{:.2}", seconds)),
+            result.enemy_bullet_deaths,
+            result.own_bullet_deaths,
+            result.unknown_deaths,
+        );
+    }
+}
+
+fn print_json(bot: &str, results: &[MissionResult]) {
+    let missions: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "{{\"mission\":{},\"runs\":{},\"wins\":{},\"losses\":{},\"timeouts\":{},\
+                 \"win_rate\":{:.3},\"average_completion_seconds\":{},\
+                 \"deaths\":{{\"enemy_bullet\":{},\"own_bullet\":{},\"unknown\":{}}}}}",
+                result.level.id(),
+                result.runs,
+                result.wins,
+                result.losses,
+                result.timeouts,
+                result.win_rate(),
+                result
+                    .average_completion_seconds()
+                    .map_or("null".to_string(), |seconds| format!("{:.2}", seconds)),
+                result.enemy_bullet_deaths,
+                result.own_bullet_deaths,
+                result.unknown_deaths,
+            )
+        })
+        .collect();
+
+    println!(
+        "{{\"bot\":\"{}\",\"missions\":[{}]}}",
+        bot.escape_default(),
+        missions.join(",")
+    );
+}
+
+// helper function to create the bot with the given name, seeded for a single run
+fn create_bot(name: &str, seed: u64) -> Option<Box<dyn Bot>> {
+    match name {
+        "idle" => Some(Box::new(IdleBot)),
+        "sniper" => Some(Box::new(SniperBot { tick: 0 })),
+        "random" => Some(Box::new(RandomBot {
+            rng: SimulationRng::new(seed),
+            tick: 0,
+            direction: Vec2::ZERO,
+        })),
+        _ => None,
+    }
+}
+
+// helper function to find the enemy closest to the player
+fn nearest_enemy(observation: &Observation) -> Option<Vec2> {
+    let player = observation.player?;
+    observation
+        .enemies
+        .iter()
+        .copied()
+        .min_by(|a, b| a.distance(player).total_cmp(&b.distance(player)))
+}
+
+// Never moves or shoots, showing how long the enemies take to win on their own
+struct IdleBot;
+
+impl Bot for IdleBot {
+    fn act(&mut self, _observation: &Observation) -> Action {
+        Action::default()
+    }
+}
+
+// Stays where it starts and keeps shooting at the nearest enemy
+struct SniperBot {
+    tick: u64,
+}
+
+impl Bot for SniperBot {
+    fn act(&mut self, observation: &Observation) -> Action {
+        self.tick += 1;
+        match nearest_enemy(observation) {
+            Some(enemy) => Action {
+                direction: Vec2::ZERO,
+                aim: enemy,
+                fire: self.tick.is_multiple_of(FIRE_INTERVAL),
+            },
+            None => Action::default(),
+        }
+    }
+}
+
+// Drives around at random and shoots at the nearest enemy every now and then
+struct RandomBot {
+    rng: SimulationRng,
+    tick: u64,
+    direction: Vec2,
+}
+
+impl Bot for RandomBot {
+    fn act(&mut self, observation: &Observation) -> Action {
+        // Pick a new direction on the first tick and every FIRE_INTERVAL ticks after it
+        if self.tick.is_multiple_of(FIRE_INTERVAL) {
+            self.direction = Vec2::new(
+                (self.rng.next_f32() * 3.0).floor() - 1.0,
+                (self.rng.next_f32() * 3.0).floor() - 1.0,
+            );
+        }
+        self.tick += 1;
+
+        Action {
+            direction: self.direction,
+            aim: nearest_enemy(observation).unwrap_or(Vec2::ZERO),
+            fire: self.rng.next_f32() < 1.0 / FIRE_INTERVAL as f32,
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Collision geometry shared by everything that moves: swept box tests, merged wall rectangles and
+// a spatial index of every tank and bullet.
+use crate::{
+    combat::{Bullet, BulletOwner},
+    simulation::{Position, TICK_SECONDS},
+};
+use bevy::{prelude::*, utils::HashMap};
+
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum Collider {
+    Wall,
+    Player,
+    Bullet,
+    Enemy,
+}
+
+#[derive(Component)]
+pub struct Hitbox(pub Vec2);
+
+// Marks the wall tiles merged into the wall geometry
+#[derive(Component)]
+pub struct Wall;
+
+// Result of sweeping a moving box against another box
+pub struct SweepHit {
+    // Fraction of the motion travelled before contact, between 0 and 1
+    pub time: f32,
+    // Normal of the surface that was hit, pointing towards the moving box
+    pub normal: Vec2,
+}
+
+// Entry times closer than this are treated as hitting both faces of a corner at once
+const CORNER_EPSILON: f32 = 1e-4;
+
+// helper function to find the earliest contact of a box moving by `motion` with a stationary box
+pub fn sweep_aabb(
+    position: Vec2,
+    size: Vec2,
+    motion: Vec2,
+    target_position: Vec2,
+    target_size: Vec2,
+) -> Option<SweepHit> {
+    // Grow the target by the size of the moving box so the moving box can be treated as a point
+    let half_extents = (size + target_size) / 2.0;
+    let min = target_position - half_extents;
+    let max = target_position + half_extents;
+
+    let mut near = Vec2::splat(f32::NEG_INFINITY);
+    let mut exit = f32::INFINITY;
+
+    for axis in 0..2 {
+        if motion[axis] == 0.0 {
+            // Not moving along this axis, so the point has to already be between the two edges
+            if position[axis] <= min[axis] || position[axis] >= max[axis] {
+                return None;
+            }
+        } else {
+            let t1 = (min[axis] - position[axis]) / motion[axis];
+            let t2 = (max[axis] - position[axis]) / motion[axis];
+            near[axis] = t1.min(t2);
+            exit = exit.min(t1.max(t2));
+        }
+    }
+
+    let entry = near.max_element();
+    if entry > exit || entry > 1.0 || exit <= 0.0 {
+        return None;
+    }
+
+    if entry < 0.0 {
+        // The boxes already overlap, so push out along the axis of least penetration
+        let offset = position - target_position;
+        let penetration = half_extents - offset.abs();
+        let normal = if penetration.x < penetration.y {
+            Vec2::new(offset.x.signum(), 0.0)
+        } else {
+            Vec2::new(0.0, offset.y.signum())
+        };
+        return Some(SweepHit { time: 0.0, normal });
+    }
+
+    // The face that was crossed last is the one that was hit. If both faces were crossed at the
+    // same time the box hit the corner exactly, and the normal points diagonally out of it.
+    let mut normal = Vec2::ZERO;
+    for axis in 0..2 {
+        if entry - near[axis] < CORNER_EPSILON {
+            normal[axis] = -motion[axis].signum();
+        }
+    }
+
+    Some(SweepHit {
+        time: entry,
+        normal: normal.normalize(),
+    })
+}
+
+// Axis-aligned rectangle covering one or more wall tiles
+#[derive(Clone, Copy)]
+pub struct WallRect {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl WallRect {
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    // Returns the union of both rectangles if together they form a single rectangle
+    fn merge(&self, other: &WallRect) -> Option<WallRect> {
+        let same = |a: f32, b: f32| (a - b).abs() < CORNER_EPSILON;
+        let touching = |a_min: f32, a_max: f32, b_min: f32, b_max: f32| {
+            a_min <= b_max + CORNER_EPSILON && b_min <= a_max + CORNER_EPSILON
+        };
+
+        let same_rows = same(self.min.y, other.min.y) && same(self.max.y, other.max.y);
+        let same_columns = same(self.min.x, other.min.x) && same(self.max.x, other.max.x);
+        if (same_rows && touching(self.min.x, self.max.x, other.min.x, other.max.x))
+            || (same_columns && touching(self.min.y, self.max.y, other.min.y, other.max.y))
+        {
+            Some(WallRect {
+                min: self.min.min(other.min),
+                max: self.max.max(other.max),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// Wall tiles merged into larger rectangles, so bullets cannot catch on the seams between tiles
+#[derive(Default)]
+pub struct WallGeometry {
+    rects: Vec<WallRect>,
+    grid: SpatialHash,
+    // Number of wall tiles the geometry was built from
+    tiles: usize,
+}
+
+impl WallGeometry {
+    pub fn query(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = &WallRect> {
+        self.grid
+            .query(min, max)
+            .into_iter()
+            .map(move |index| &self.rects[index])
+    }
+}
+
+// helper function to merge adjacent wall tiles until no two rectangles can be combined
+fn merge_wall_rects(mut rects: Vec<WallRect>) -> Vec<WallRect> {
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..rects.len() {
+            for j in (i + 1)..rects.len() {
+                if let Some(rect) = rects[i].merge(&rects[j]) {
+                    rects[i] = rect;
+                    rects.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    rects
+}
+
+pub fn wall_geometry_system(
+    mut wall_geometry: ResMut<WallGeometry>,
+    added_query: Query<(), (With<Wall>, Added<Collider>)>,
+    query: Query<(&Position, &Hitbox), With<Wall>>,
+) {
+    // Only rebuild the geometry when walls have been spawned or despawned. Despawns are detected
+    // by the tile count, since removals can be cleared between two ticks.
+    let tile_count = query.iter().count();
+    if added_query.is_empty() && tile_count == wall_geometry.tiles {
+        return;
+    }
+
+    let tiles: Vec<WallRect> = query
+        .iter()
+        .map(|(position, hitbox)| WallRect {
+            min: position.0 - hitbox.0 / 2.0,
+            max: position.0 + hitbox.0 / 2.0,
+        })
+        .collect();
+    let rects = merge_wall_rects(tiles);
+    let mut grid = SpatialHash::default();
+    for (index, rect) in rects.iter().enumerate() {
+        grid.insert(index, rect.min, rect.max);
+    }
+
+    *wall_geometry = WallGeometry {
+        rects,
+        grid,
+        tiles: tile_count,
+    };
+}
+
+// Width and height of a single spatial hash cell
+const SPATIAL_CELL_SIZE: f32 = 64.0;
+
+// Uniform grid that buckets items by the cells their bounds overlap
+#[derive(Default)]
+struct SpatialHash {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    fn cells(min: Vec2, max: Vec2) -> impl Iterator<Item = (i32, i32)> {
+        let min = (min / SPATIAL_CELL_SIZE).floor();
+        let max = (max / SPATIAL_CELL_SIZE).floor();
+        (min.x as i32..=max.x as i32)
+            .flat_map(move |x| (min.y as i32..=max.y as i32).map(move |y| (x, y)))
+    }
+
+    fn insert(&mut self, index: usize, min: Vec2, max: Vec2) {
+        for cell in Self::cells(min, max) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    // Returns the index of every item sharing a cell with the given bounds, in insertion order
+    fn query(&self, min: Vec2, max: Vec2) -> Vec<usize> {
+        let mut found: Vec<usize> = Self::cells(min, max)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+// helper function to get the corners of a box
+pub fn bounds(position: Vec2, size: Vec2) -> (Vec2, Vec2) {
+    (position - size / 2.0, position + size / 2.0)
+}
+
+// A non-wall collider as it was at the start of the tick
+pub struct ColliderEntry {
+    pub entity: Entity,
+    pub collider: Collider,
+    pub position: Vec2,
+    pub size: Vec2,
+    pub velocity: Vec2,
+}
+
+// Spatial index of every tank and bullet, rebuilt at the start of each tick
+#[derive(Default)]
+pub struct ColliderIndex {
+    entries: Vec<ColliderEntry>,
+    grid: SpatialHash,
+    bullet_counts: HashMap<Entity, usize>,
+}
+
+impl ColliderIndex {
+    pub fn query(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = &ColliderEntry> {
+        self.grid
+            .query(min, max)
+            .into_iter()
+            .map(move |index| &self.entries[index])
+    }
+
+    // Number of bullets currently in flight that were fired by the given tank
+    pub fn bullet_count(&self, owner: Entity) -> usize {
+        self.bullet_counts.get(&owner).copied().unwrap_or(0)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn collider_index_system(
+    mut collider_index: ResMut<ColliderIndex>,
+    query: Query<(
+        Entity,
+        &Collider,
+        &Position,
+        &Hitbox,
+        Option<&Bullet>,
+        Option<&BulletOwner>,
+    )>,
+) {
+    let collider_index = &mut *collider_index;
+    collider_index.entries.clear();
+    collider_index.grid = SpatialHash::default();
+    collider_index.bullet_counts.clear();
+
+    for (entity, collider, position, hitbox, bullet, bullet_owner) in query.iter() {
+        // Walls are indexed separately as merged geometry
+        if *collider == Collider::Wall {
+            continue;
+        }
+
+        let position = position.0;
+        let velocity = bullet.map_or(Vec2::ZERO, |bullet| bullet.velocity);
+
+        // Index moving colliders over the whole area they can cover this tick
+        let (start_min, start_max) = bounds(position, hitbox.0);
+        let (end_min, end_max) = bounds(position + velocity * TICK_SECONDS as f32, hitbox.0);
+        collider_index.grid.insert(
+            collider_index.entries.len(),
+            start_min.min(end_min),
+            start_max.max(end_max),
+        );
+        collider_index.entries.push(ColliderEntry {
+            entity,
+            collider: *collider,
+            position,
+            size: hitbox.0,
+            velocity,
+        });
+
+        if let Some(bullet_owner) = bullet_owner {
+            *collider_index
+                .bullet_counts
+                .entry(bullet_owner.0)
+                .or_default() += 1;
+        }
+    }
+}
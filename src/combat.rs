@@ -0,0 +1,405 @@
+// Weapons and bullets: how tanks fire, and how bullets fly, ricochet off walls and destroy whatever
+// they run into.
+use crate::{
+    collision::{
+        bounds, sweep_aabb, Collider, ColliderEntry, ColliderIndex, Hitbox, SweepHit, WallGeometry,
+    },
+    config::GameConfig,
+    flow::GameState,
+    level::Creator,
+    simulation::{Position, SimulationStage, SimulationSystem, TickStage, TICK_SECONDS},
+};
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+// Size of the play area, matching the default window size. Bullets leaving it are removed. The
+// arena keeps its size in windows of any other size, and is scaled to fit them instead.
+pub const ARENA_WIDTH: f32 = 1280.0;
+pub const ARENA_HEIGHT: f32 = 720.0;
+
+// helper function to find how many arena units a single pixel covers when the whole arena is
+// fitted into a window of the given size
+pub fn arena_units_per_pixel(width: f32, height: f32) -> f32 {
+    (ARENA_WIDTH / width).max(ARENA_HEIGHT / height)
+}
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FireIntent>()
+            .add_event::<BulletFired>()
+            .add_event::<BulletRicocheted>()
+            .add_event::<BulletsCancelled>()
+            .add_event::<TankDestroyed>()
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule.add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_update(GameState::Playing)
+                        .with_system(
+                            weapon_fire_system
+                                .label(SimulationSystem::Firing)
+                                .after(SimulationSystem::Ai),
+                        )
+                        .with_system(
+                            bullet_collision_system
+                                .label(SimulationSystem::Collision)
+                                .after(SimulationSystem::Movement),
+                        )
+                        .with_system(
+                            bullet_cleanup_system
+                                .label(SimulationSystem::Resolution)
+                                .after(SimulationSystem::Collision),
+                        )
+                        .with_system(
+                            tank_destroyed_system
+                                .label(SimulationSystem::Resolution)
+                                .after(SimulationSystem::Collision),
+                        ),
+                )
+            });
+    }
+}
+
+// A tank wants to fire its weapon. The shot only goes off if the weapon has cooled down and the
+// tank has fewer bullets in the air than the weapon's capacity.
+#[derive(Clone, Debug)]
+pub struct FireIntent {
+    pub tank: Entity,
+    // Direction to fire in, which does not need to be normalized
+    pub direction: Vec2,
+}
+
+// A tank fired a bullet
+#[derive(Clone, Debug)]
+pub struct BulletFired {
+    pub bullet: Entity,
+    // Tank that fired the bullet
+    pub owner: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+// A bullet bounced off a wall
+#[derive(Clone, Debug)]
+pub struct BulletRicocheted {
+    pub bullet: Entity,
+    pub position: Vec2,
+    // Normal of the wall surface that was hit
+    pub normal: Vec2,
+    // Number of times the bullet has ricocheted so far, including this one
+    pub ricochets: u32,
+}
+
+// Two bullets ran into each other and were both destroyed
+#[derive(Clone, Debug)]
+pub struct BulletsCancelled {
+    pub first: Entity,
+    pub second: Entity,
+    pub position: Vec2,
+}
+
+// A tank was hit by a bullet. The tank is despawned during the same tick.
+#[derive(Clone, Debug)]
+pub struct TankDestroyed {
+    pub victim: Entity,
+    // Tank that fired the bullet, which may be the victim itself after a ricochet
+    pub killer: Entity,
+    pub bullet: Entity,
+    pub position: Vec2,
+}
+
+#[derive(Component)]
+pub struct Bullet {
+    pub velocity: Vec2,
+}
+
+#[derive(Component)]
+pub struct RicochetLimit(pub u32);
+
+#[derive(Component)]
+pub struct RicochetCount(pub u32);
+
+#[derive(Component)]
+pub struct BulletOwner(pub Entity);
+
+// Kinds of projectile a weapon can fire
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum Projectile {
+    Bullet,
+}
+
+impl Projectile {
+    pub fn texture(&self) -> &'static str {
+        match self {
+            Projectile::Bullet => "bullet.png",
+        }
+    }
+}
+
+// The weapon of a tank, fired through `FireIntent` events
+#[derive(Component, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Weapon {
+    pub projectile: Projectile,
+    // Speed of fired projectiles in units per second
+    pub speed: f32,
+    // Number of times a projectile bounces off walls before it is destroyed
+    pub ricochets: u32,
+    // Seconds between two shots
+    pub cooldown: f32,
+    // Number of projectiles the tank can have in the air at once
+    pub capacity: u8,
+    // Seconds left until the weapon can fire again
+    #[serde(skip)]
+    pub reload: f32,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            projectile: Projectile::Bullet,
+            speed: 150.0,
+            ricochets: 1,
+            cooldown: 0.0,
+            capacity: 1,
+            reload: 0.0,
+        }
+    }
+}
+
+// Maximum number of contacts a single bullet resolves within one tick
+const MAX_BULLET_CONTACTS_PER_TICK: usize = 8;
+
+fn weapon_fire_system(
+    commands: Commands,
+    config: Res<GameConfig>,
+    mut intents: EventReader<FireIntent>,
+    mut weapon_query: Query<(&mut Weapon, &Position)>,
+    collider_index: Res<ColliderIndex>,
+    mut fired_events: EventWriter<BulletFired>,
+) {
+    for (mut weapon, _) in weapon_query.iter_mut() {
+        weapon.reload = (weapon.reload - TICK_SECONDS as f32).max(0.0);
+    }
+
+    let mut creator = Creator {
+        commands,
+        config: &config,
+    };
+    let mut fired = HashSet::default();
+    for intent in intents.iter() {
+        let (mut weapon, position) = match weapon_query.get_mut(intent.tank) {
+            Ok(weapon) => weapon,
+            Err(_) => continue,
+        };
+
+        // Tanks fire at most once per tick, since bullets fired this tick are not in the collider
+        // index yet
+        if weapon.reload > 0.0
+            || fired.contains(&intent.tank)
+            || collider_index.bullet_count(intent.tank) >= weapon.capacity.into()
+        {
+            continue;
+        }
+
+        if let Some(direction) = intent.direction.try_normalize() {
+            let velocity = weapon.speed * direction;
+            let bullet = creator.create_projectile(
+                weapon.projectile,
+                position.0,
+                velocity,
+                weapon.ricochets,
+                intent.tank,
+            );
+            weapon.reload = weapon.cooldown;
+            fired.insert(intent.tank);
+            fired_events.send(BulletFired {
+                bullet,
+                owner: intent.tank,
+                position: position.0,
+                velocity,
+            });
+        }
+    }
+}
+
+fn bullet_collision_system(
+    mut commands: Commands,
+    wall_geometry: Res<WallGeometry>,
+    collider_index: Res<ColliderIndex>,
+    mut ricocheted_events: EventWriter<BulletRicocheted>,
+    mut cancelled_events: EventWriter<BulletsCancelled>,
+    mut tank_destroyed_events: EventWriter<TankDestroyed>,
+    mut bullet_query: Query<(
+        Entity,
+        &mut Bullet,
+        &BulletOwner,
+        &RicochetLimit,
+        &mut RicochetCount,
+        &mut Position,
+        &Hitbox,
+    )>,
+) {
+    let delta = TICK_SECONDS as f32;
+    let mut destroyed: HashSet<Entity> = HashSet::default();
+
+    for (
+        bullet_entity,
+        mut bullet,
+        bullet_owner,
+        ricochet_limit,
+        mut ricochet_count,
+        mut bullet_position,
+        bullet_hitbox,
+    ) in bullet_query.iter_mut()
+    {
+        if destroyed.contains(&bullet_entity) {
+            continue;
+        }
+
+        let mut position = bullet_position.0;
+        let mut velocity = bullet.velocity;
+        // Time in seconds this bullet has already travelled during this tick
+        let mut elapsed = 0.0;
+
+        // Move the bullet from contact to contact until it has used up the tick's time
+        for _ in 0..MAX_BULLET_CONTACTS_PER_TICK {
+            let remaining = delta - elapsed;
+            let motion = velocity * remaining;
+            let mut earliest: Option<(SweepHit, Option<&ColliderEntry>)> = None;
+
+            // Only look at colliders near the path of the bullet
+            let (start_min, start_max) = bounds(position, bullet_hitbox.0);
+            let (end_min, end_max) = bounds(position + motion, bullet_hitbox.0);
+            let (min, max) = (start_min.min(end_min), start_max.max(end_max));
+
+            for rect in wall_geometry.query(min, max) {
+                if let Some(hit) = sweep_aabb(
+                    position,
+                    bullet_hitbox.0,
+                    motion,
+                    rect.center(),
+                    rect.size(),
+                ) {
+                    // Ignore walls the bullet is already moving away from
+                    if motion.dot(hit.normal) < 0.0
+                        && earliest
+                            .as_ref()
+                            .is_none_or(|(earliest_hit, _)| hit.time < earliest_hit.time)
+                    {
+                        earliest = Some((hit, None));
+                    }
+                }
+            }
+
+            for other in collider_index.query(min, max) {
+                if other.entity == bullet_entity || destroyed.contains(&other.entity) {
+                    continue;
+                }
+
+                // Make sure freshly fired bullets do not kill the tank that fired it
+                if other.entity == bullet_owner.0 && ricochet_count.0 < 1 {
+                    continue;
+                }
+
+                // Sweep relative to the other collider so moving bullets can hit each other
+                let other_position = other.position + other.velocity * elapsed;
+                let relative_motion = (velocity - other.velocity) * remaining;
+                if let Some(hit) = sweep_aabb(
+                    position,
+                    bullet_hitbox.0,
+                    relative_motion,
+                    other_position,
+                    other.size,
+                ) {
+                    if earliest
+                        .as_ref()
+                        .is_none_or(|(earliest_hit, _)| hit.time < earliest_hit.time)
+                    {
+                        earliest = Some((hit, Some(other)));
+                    }
+                }
+            }
+
+            let (hit, other) = match earliest {
+                Some(contact) => contact,
+                None => {
+                    position += motion;
+                    break;
+                }
+            };
+
+            // Advance the bullet to the exact point of contact
+            position += motion * hit.time;
+            elapsed += remaining * hit.time;
+
+            match other {
+                // Bullets destroy each other and tanks on contact
+                Some(other) => {
+                    if other.collider == Collider::Bullet {
+                        commands.entity(other.entity).despawn();
+                        cancelled_events.send(BulletsCancelled {
+                            first: bullet_entity,
+                            second: other.entity,
+                            position,
+                        });
+                    } else {
+                        tank_destroyed_events.send(TankDestroyed {
+                            victim: other.entity,
+                            killer: bullet_owner.0,
+                            bullet: bullet_entity,
+                            position: other.position,
+                        });
+                    }
+
+                    commands.entity(bullet_entity).despawn();
+                    destroyed.insert(bullet_entity);
+                    destroyed.insert(other.entity);
+                    break;
+                }
+                None => {
+                    // Destroy bullets if they have reached their ricochet limit
+                    if ricochet_count.0 >= ricochet_limit.0 {
+                        commands.entity(bullet_entity).despawn();
+                        destroyed.insert(bullet_entity);
+                        break;
+                    }
+
+                    // A single reflection about the contact normal, even at corners and seams,
+                    // counts as exactly one ricochet
+                    velocity -= 2.0 * velocity.dot(hit.normal) * hit.normal;
+                    ricochet_count.0 += 1;
+                    ricocheted_events.send(BulletRicocheted {
+                        bullet: bullet_entity,
+                        position,
+                        normal: hit.normal,
+                        ricochets: ricochet_count.0,
+                    });
+                }
+            }
+        }
+
+        bullet_position.0 = position;
+        bullet.velocity = velocity;
+    }
+}
+
+fn bullet_cleanup_system(mut commands: Commands, query: Query<(Entity, &Position), With<Bullet>>) {
+    // Delete bullets if they are outside of the arena
+    for (entity, position) in query.iter() {
+        if position.0.x > ARENA_WIDTH / 2.0
+            || position.0.x < 0.0 - ARENA_WIDTH / 2.0
+            || position.0.y > ARENA_HEIGHT / 2.0
+            || position.0.y < 0.0 - ARENA_HEIGHT / 2.0
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn tank_destroyed_system(mut commands: Commands, mut events: EventReader<TankDestroyed>) {
+    for event in events.iter() {
+        commands.entity(event.victim).despawn();
+    }
+}
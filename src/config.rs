@@ -0,0 +1,207 @@
+// Gameplay constants designers can tune without recompiling.
+//
+// The simulation always starts out with the defaults below, so tests, bots and replays behave the
+// same everywhere. The game loads `assets/config.ron` on top of them at startup, and reloads it
+// whenever the file changes. Changes to tanks and weapons apply from the next mission.
+use crate::{ai::EnemyKind, combat::Weapon};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::{fs, path::PathBuf, time::SystemTime};
+
+// File the game reads the config from
+pub const CONFIG_PATH: &str = "assets/config.ron";
+
+// Seconds between checks for changes to the config file
+const RELOAD_INTERVAL: f32 = 1.0;
+
+// Every value missing from the config file keeps its default
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub player: PlayerConfig,
+    pub brown_tank: EnemyConfig,
+    pub score: ScoreConfig,
+    // Size of the hitbox of bullets
+    pub bullet_hitbox: Vec2,
+    // Seconds the "Mission complete!" screen is shown before the next mission
+    pub win_seconds: f32,
+    // Seconds before a lost mission restarts
+    pub lose_seconds: f32,
+    // Seconds the mission briefing is shown before the tanks start moving
+    pub briefing_seconds: f32,
+    // Seconds the "Game Over" screen is shown before returning to the main menu
+    pub game_over_seconds: f32,
+    // Seconds the summary after the last mission is shown before returning to the main menu
+    pub campaign_complete_seconds: f32,
+    // Lives the player starts a campaign with
+    pub starting_lives: u32,
+    // Number of missions to clear for every bonus life, or 0 for none
+    pub bonus_life_missions: u32,
+    // Show the frame rate in the corner of the window when the game starts
+    pub show_fps: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PlayerConfig {
+    // Speed in units per second
+    pub speed: f32,
+    pub hitbox: Vec2,
+    pub weapon: Weapon,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EnemyConfig {
+    pub hitbox: Vec2,
+    pub weapon: Weapon,
+    // Points the player scores for destroying the tank
+    pub points: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ScoreConfig {
+    // Points on top of the tank's own for destroying it with a bullet that ricocheted first
+    pub ricochet_kill_bonus: u32,
+    // Points for a player bullet destroyed by running into another bullet
+    pub cancel_bonus: u32,
+    // Seconds after a kill during which the next one continues the combo
+    pub combo_seconds: f32,
+    // Points for every kill in a combo after the first, times the kills before it
+    pub combo_bonus: u32,
+    // Seconds a mission should take. Winning faster multiplies the mission's score.
+    pub par_seconds: f32,
+    // Multiplier for winning a mission instantly, dropping to 1 at the par time
+    pub max_time_multiplier: f32,
+}
+
+impl GameConfig {
+    pub fn enemy(&self, kind: EnemyKind) -> &EnemyConfig {
+        match kind {
+            EnemyKind::Brown => &self.brown_tank,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            player: PlayerConfig::default(),
+            brown_tank: EnemyConfig::default(),
+            score: ScoreConfig::default(),
+            bullet_hitbox: Vec2::new(8.0, 8.0),
+            win_seconds: 4.0,
+            lose_seconds: 4.0,
+            briefing_seconds: 3.0,
+            game_over_seconds: 5.0,
+            campaign_complete_seconds: 6.0,
+            starting_lives: 3,
+            bonus_life_missions: 5,
+            show_fps: true,
+        }
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        PlayerConfig {
+            speed: 100.0,
+            hitbox: Vec2::new(32.0, 32.0),
+            weapon: Weapon {
+                capacity: 5,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        EnemyConfig {
+            hitbox: Vec2::new(32.0, 32.0),
+            weapon: Weapon {
+                capacity: 1,
+                ..Default::default()
+            },
+            points: 100,
+        }
+    }
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        ScoreConfig {
+            ricochet_kill_bonus: 50,
+            cancel_bonus: 10,
+            combo_seconds: 2.0,
+            combo_bonus: 50,
+            par_seconds: 60.0,
+            max_time_multiplier: 2.0,
+        }
+    }
+}
+
+// Loads the config file at startup and reloads it whenever it changes
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let mut watcher = ConfigWatcher {
+            path: PathBuf::from(CONFIG_PATH),
+            modified: None,
+            timer: Timer::from_seconds(RELOAD_INTERVAL, true),
+        };
+        if let Some(config) = watcher.load() {
+            app.insert_resource(config);
+        }
+
+        app.insert_resource(watcher)
+            .add_system(config_reload_system);
+    }
+}
+
+struct ConfigWatcher {
+    path: PathBuf,
+    // Modification time of the file when it was last loaded
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl ConfigWatcher {
+    // Reads the config file, unless it is unchanged since the last time. Errors are reported and
+    // leave the current config in place.
+    fn load(&mut self) -> Option<GameConfig> {
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+        match fs::read_to_string(&self.path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|error| error.to_string()))
+        {
+            Ok(config) => Some(config),
+            Err(error) => {
+                warn!("Failed to load config {}: {}", self.path.display(), error);
+                None
+            }
+        }
+    }
+}
+
+fn config_reload_system(
+    time: Res<Time>,
+    mut watcher: ResMut<ConfigWatcher>,
+    mut config: ResMut<GameConfig>,
+) {
+    if watcher.timer.tick(time.delta()).just_finished() {
+        if let Some(new_config) = watcher.load() {
+            *config = new_config;
+            info!("Reloaded config {}", watcher.path.display());
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// Gym-style environment for training and evaluating bots against the enemy tank AI.
+//
+// Every call to `reset` builds a fresh headless simulation of a single mission, and every call to
+// `step` feeds one action to the player's tank and runs exactly one simulation tick. An episode
+// ends as soon as the mission is won or lost.
+use crate::{
+    ai::Enemy,
+    collision::Collider,
+    combat::{Bullet, BulletOwner, TankDestroyed},
+    flow::{GameState, MissionEnded},
+    level::{CurrentLevel, Level},
+    player::{CursorPosition, Player, PlayerInput},
+    simulation::{Position, SimulationClock, SimulationSeed},
+    SimulationPlugins,
+};
+use bevy::{
+    app::{Events, ManualEventReader},
+    prelude::*,
+};
+
+// Reward for every enemy tank destroyed
+const ENEMY_DESTROYED_REWARD: f32 = 1.0;
+
+// Reward for the player's tank being destroyed
+const PLAYER_DESTROYED_REWARD: f32 = -1.0;
+
+// Controls of the player's tank for a single tick, mirroring the keyboard and mouse
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Action {
+    // Direction to drive in, with each axis between -1 and 1 like the WASD keys
+    pub direction: Vec2,
+    // Point in the arena to aim at, like the mouse cursor
+    pub aim: Vec2,
+    // Fire a bullet towards the aim point, like a left click
+    pub fire: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BulletObservation {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    // Whether the bullet was fired by the player rather than by an enemy
+    pub player_owned: bool,
+}
+
+// Everything a bot can see of the arena after a tick
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Observation {
+    // Simulation tick the observation was taken at
+    pub tick: u64,
+    // Position of the player's tank, unless it was destroyed
+    pub player: Option<Vec2>,
+    pub enemies: Vec<Vec2>,
+    pub bullets: Vec<BulletObservation>,
+    // Center of every wall tile
+    pub walls: Vec<Vec2>,
+}
+
+// What destroyed the player's tank
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeathCause {
+    EnemyBullet,
+    // One of the player's own bullets after it ricocheted
+    OwnBullet,
+}
+
+// A player controlled by code instead of the keyboard and mouse
+pub trait Bot {
+    fn act(&mut self, observation: &Observation) -> Action;
+}
+
+pub struct Env {
+    app: App,
+    done: bool,
+    death_cause: Option<DeathCause>,
+    tank_destroyed_reader: ManualEventReader<TankDestroyed>,
+    mission_ended_reader: ManualEventReader<MissionEnded>,
+    // Observation returned by the last reset or step
+    observation: Observation,
+}
+
+impl Env {
+    // Creates an environment with no episode in progress, so `reset` has to start one before the
+    // first step
+    pub fn new() -> Env {
+        Env {
+            app: App::new(),
+            done: true,
+            death_cause: None,
+            tank_destroyed_reader: Default::default(),
+            mission_ended_reader: Default::default(),
+            observation: Observation::default(),
+        }
+    }
+
+    // Starts a new episode on the given level, returning the arena as the mission starts
+    pub fn reset(&mut self, seed: u64, level: Level) -> Observation {
+        let mut app = App::new();
+        app.insert_resource(SimulationClock {
+            stepped: true,
+            ..Default::default()
+        })
+        .add_plugins(MinimalPlugins)
+        .add_plugins(SimulationPlugins)
+        .insert_resource(CurrentLevel(Some(level)))
+        .insert_resource(SimulationSeed(seed));
+
+        // The first tick sets up the mission, and nothing happens until its briefing is over
+        app.update();
+        while *app
+            .world
+            .get_resource::<State<GameState>>()
+            .expect("Error: Failed to get game state")
+            .current()
+            == GameState::Briefing
+        {
+            app.update();
+        }
+
+        self.app = app;
+        self.done = false;
+        self.death_cause = None;
+        self.tank_destroyed_reader = Default::default();
+        self.mission_ended_reader = Default::default();
+        self.observation = self.observe();
+        self.observation.clone()
+    }
+
+    // Runs a single tick with the given action. Returns the arena afterwards, the reward earned
+    // during the tick and whether the episode is over. Once it is over the simulation no longer
+    // advances until the next reset.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observation.clone(), 0.0, true);
+        }
+
+        let enemies_before = self.observation.enemies.len();
+        let player_before = self.player();
+
+        let world = &mut self.app.world;
+        world
+            .get_resource_mut::<CursorPosition>()
+            .expect("Error: Failed to get cursor position")
+            .pos = action.aim;
+        let mut player_input = world
+            .get_resource_mut::<PlayerInput>()
+            .expect("Error: Failed to get player input");
+        player_input.direction = action.direction;
+        player_input.fire = action.fire;
+
+        self.app.update();
+
+        let observation = self.observe();
+        let mut reward = enemies_before.saturating_sub(observation.enemies.len()) as f32
+            * ENEMY_DESTROYED_REWARD;
+        if let (Some(player), None) = (player_before, observation.player) {
+            reward += PLAYER_DESTROYED_REWARD;
+            self.death_cause = self.find_death_cause(player);
+        }
+
+        // The state only changes on the tick after the mission ends, so go by the event sent on
+        // the tick it ended instead
+        let events = self
+            .app
+            .world
+            .get_resource::<Events<MissionEnded>>()
+            .expect("Error: Failed to get mission ended events");
+        self.done = self.mission_ended_reader.iter(events).next().is_some();
+
+        self.observation = observation.clone();
+        (observation, reward, self.done)
+    }
+
+    // What destroyed the player's tank, once the episode was lost
+    pub fn death_cause(&self) -> Option<DeathCause> {
+        self.death_cause
+    }
+
+    // helper function to find the player's tank, unless it was destroyed
+    fn player(&mut self) -> Option<Entity> {
+        let world = &mut self.app.world;
+        world
+            .query_filtered::<Entity, With<Player>>()
+            .iter(world)
+            .next()
+    }
+
+    // helper function to tell whose bullet destroyed the player's tank during the last tick
+    fn find_death_cause(&mut self, player: Entity) -> Option<DeathCause> {
+        let events = self
+            .app
+            .world
+            .get_resource::<Events<TankDestroyed>>()
+            .expect("Error: Failed to get tank destroyed events");
+        self.tank_destroyed_reader
+            .iter(events)
+            .find(|event| event.victim == player)
+            .map(|event| {
+                if event.killer == player {
+                    DeathCause::OwnBullet
+                } else {
+                    DeathCause::EnemyBullet
+                }
+            })
+    }
+
+    fn observe(&mut self) -> Observation {
+        let world = &mut self.app.world;
+        let tick = world
+            .get_resource::<SimulationClock>()
+            .expect("Error: Failed to get simulation clock")
+            .tick;
+
+        let player = world
+            .query_filtered::<(Entity, &Position), With<Player>>()
+            .iter(world)
+            .next()
+            .map(|(entity, position)| (entity, position.0));
+
+        let enemies = world
+            .query_filtered::<&Position, With<Enemy>>()
+            .iter(world)
+            .map(|position| position.0)
+            .collect();
+
+        let bullets = world
+            .query::<(&Bullet, &BulletOwner, &Position)>()
+            .iter(world)
+            .map(|(bullet, bullet_owner, position)| BulletObservation {
+                position: position.0,
+                velocity: bullet.velocity,
+                player_owned: player.is_some_and(|(entity, _)| entity == bullet_owner.0),
+            })
+            .collect();
+
+        let walls = world
+            .query::<(&Collider, &Position)>()
+            .iter(world)
+            .filter(|(collider, _)| **collider == Collider::Wall)
+            .map(|(_, position)| position.0)
+            .collect();
+
+        Observation {
+            tick,
+            player: player.map(|(_, position)| position),
+            enemies,
+            bullets,
+            walls,
+        }
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env::new()
+    }
+}
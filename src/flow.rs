@@ -0,0 +1,398 @@
+// The state of the current mission and campaign, the rules deciding when a mission is won or lost,
+// and what happens afterwards.
+use crate::{
+    ai::Enemy,
+    config::GameConfig,
+    level::{CurrentLevel, Level},
+    player::Player,
+    simulation::{SimulationStage, SimulationSystem, TickStage, TICK_SECONDS},
+    ui::UiElement,
+};
+use bevy::{ecs::schedule::ShouldRun, prelude::*, render::camera::Camera};
+use std::time::Duration;
+
+pub struct GameFlowPlugin;
+
+impl Plugin for GameFlowPlugin {
+    fn build(&self, app: &mut App) {
+        // The game may start in another state, such as the main menu, by inserting it before this
+        // plugin is added
+        if !app.world.contains_resource::<State<GameState>>() {
+            app.insert_resource(State::new(GameState::Briefing));
+        }
+
+        app.add_event::<MissionEnded>()
+            .add_event::<StartCampaign>()
+            .init_resource::<Campaign>()
+            .add_system(start_campaign_system)
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(TickStage::Update, State::<GameState>::get_driver())
+                    .add_system_to_stage(
+                        TickStage::Rules,
+                        playing_system.label(SimulationSystem::Rules),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Briefing).with_system(briefing_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Briefing).with_system(briefing_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Briefing).with_system(timer_cleanup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Lose).with_system(lose_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Lose).with_system(lose_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Lose).with_system(teardown_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Win).with_system(win_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Win).with_system(win_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Win).with_system(teardown_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::GameOver)
+                            .with_system(game_over_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::GameOver)
+                            .with_system(return_to_menu_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::GameOver).with_system(teardown_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::CampaignComplete)
+                            .with_system(campaign_complete_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::CampaignComplete)
+                            .with_system(return_to_menu_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::CampaignComplete)
+                            .with_system(teardown_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Restarting).with_system(restart_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Restarting).with_system(teardown_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::MainMenu).with_system(teardown_system),
+                    )
+            });
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum GameState {
+    MainMenu,
+    Win,
+    Lose,
+    // The campaign was lost with no lives left
+    GameOver,
+    // The last mission of the campaign was won
+    CampaignComplete,
+    // The mission is set up, but its tanks are frozen until the briefing is over
+    Briefing,
+    Playing,
+    // Pushed on top of Playing, freezing the mission until it is popped again
+    Paused,
+    // Tearing down the current mission to set it up again from the start
+    Restarting,
+}
+
+// The current mission was won or lost
+#[derive(Clone, Debug)]
+pub struct MissionEnded {
+    pub level: Option<Level>,
+    pub won: bool,
+}
+
+// Starts a new campaign at the given level, with full lives
+pub struct StartCampaign {
+    pub level: Level,
+}
+
+// Progress through the campaign across missions
+pub struct Campaign {
+    pub lives: u32,
+    // Number of missions won so far
+    pub missions_cleared: u32,
+}
+
+impl Campaign {
+    pub fn new(config: &GameConfig) -> Campaign {
+        Campaign {
+            lives: config.starting_lives,
+            missions_cleared: 0,
+        }
+    }
+}
+
+impl FromWorld for Campaign {
+    fn from_world(world: &mut World) -> Self {
+        Campaign::new(
+            world
+                .get_resource::<GameConfig>()
+                .expect("Error: Failed to get game config"),
+        )
+    }
+}
+
+#[derive(Component)]
+struct GameTimer(Timer);
+
+fn playing_system(
+    player_query: Query<&Player>,
+    enemy_query: Query<&Enemy>,
+    mut game_state: ResMut<State<GameState>>,
+    current_level: Res<CurrentLevel>,
+    mut mission_ended_events: EventWriter<MissionEnded>,
+) {
+    // If there are no more enemies set state to Win, otherwise, if the player was destroyed, set
+    // state to lose.
+    // In the unlikely event of a tie, the player win takes precedence for a less frustrating
+    // experience :)
+    if enemy_query.iter().count() == 0 {
+        game_state
+            .set(GameState::Win)
+            .expect("Error: Failed to push Win state");
+        mission_ended_events.send(MissionEnded {
+            level: current_level.0,
+            won: true,
+        });
+    } else if player_query.get_single().is_err() {
+        game_state
+            .set(GameState::Lose)
+            .expect("Error: Failed to push Lose state");
+        mission_ended_events.send(MissionEnded {
+            level: current_level.0,
+            won: false,
+        });
+    }
+}
+
+pub fn playing_state_criteria(game_state: Res<State<GameState>>) -> ShouldRun {
+    if *game_state.current() == GameState::Playing {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// Teardown system
+// Clean-up all entities, excluding camera and UI elements
+fn teardown_system(
+    mut commands: Commands,
+    entities: Query<Entity, (Without<Camera>, Without<UiElement>)>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn start_campaign_system(
+    mut events: EventReader<StartCampaign>,
+    config: Res<GameConfig>,
+    mut campaign: ResMut<Campaign>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for event in events.iter() {
+        *campaign = Campaign::new(&config);
+        current_level.0 = Some(event.level);
+        game_state
+            .overwrite_set(GameState::Briefing)
+            .expect("Error: Failed to set Briefing state");
+    }
+}
+
+// Clean-up the timer of the state being left, keeping the mission as it is
+fn timer_cleanup_system(mut commands: Commands, query: Query<Entity, With<GameTimer>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Restarting state systems
+fn restart_system(mut game_state: ResMut<State<GameState>>) {
+    game_state
+        .set(GameState::Briefing)
+        .expect("Error: Failed to set Briefing state");
+}
+
+// Briefing state systems
+fn briefing_setup_system(mut commands: Commands, config: Res<GameConfig>) {
+    // Start a timer
+    commands.spawn().insert(GameTimer(Timer::from_seconds(
+        config.briefing_seconds,
+        false,
+    )));
+}
+
+fn briefing_system(mut query: Query<&mut GameTimer>, mut game_state: ResMut<State<GameState>>) {
+    // Start the mission after timer reaches 0
+    if let Ok(mut timer) = query.get_single_mut() {
+        if timer
+            .0
+            .tick(Duration::from_secs_f64(TICK_SECONDS))
+            .just_finished()
+        {
+            game_state
+                .set(GameState::Playing)
+                .expect("Error: Failed to set Playing state");
+        }
+    }
+}
+
+// Lose state systems
+fn lose_setup_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut campaign: ResMut<Campaign>,
+) {
+    campaign.lives = campaign.lives.saturating_sub(1);
+
+    // Start a timer
+    commands
+        .spawn()
+        .insert(GameTimer(Timer::from_seconds(config.lose_seconds, false)));
+}
+
+fn lose_system(
+    mut query: Query<&mut GameTimer>,
+    campaign: Res<Campaign>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // Reset current level if timer reaches 0, or end the campaign if there are no lives left
+    if let Ok(mut timer) = query.get_single_mut() {
+        if timer
+            .0
+            .tick(Duration::from_secs_f64(TICK_SECONDS))
+            .just_finished()
+        {
+            if campaign.lives > 0 {
+                game_state
+                    .set(GameState::Briefing)
+                    .expect("Error: Failed to set Briefing state");
+            } else {
+                game_state
+                    .set(GameState::GameOver)
+                    .expect("Error: Failed to set GameOver state");
+            }
+        }
+    }
+}
+
+// Game over state systems
+fn game_over_setup_system(mut commands: Commands, config: Res<GameConfig>) {
+    // Start a timer
+    commands.spawn().insert(GameTimer(Timer::from_seconds(
+        config.game_over_seconds,
+        false,
+    )));
+}
+
+// Campaign complete state systems
+fn campaign_complete_setup_system(mut commands: Commands, config: Res<GameConfig>) {
+    // Start a timer
+    commands.spawn().insert(GameTimer(Timer::from_seconds(
+        config.campaign_complete_seconds,
+        false,
+    )));
+}
+
+fn return_to_menu_system(
+    mut query: Query<&mut GameTimer>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // Return to the main menu after timer reaches 0
+    if let Ok(mut timer) = query.get_single_mut() {
+        if timer
+            .0
+            .tick(Duration::from_secs_f64(TICK_SECONDS))
+            .just_finished()
+        {
+            game_state
+                .set(GameState::MainMenu)
+                .expect("Error: Failed to set MainMenu state");
+        }
+    }
+}
+
+// Win state systems
+fn win_setup_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut campaign: ResMut<Campaign>,
+) {
+    campaign.missions_cleared += 1;
+    if config.bonus_life_missions > 0
+        && campaign
+            .missions_cleared
+            .is_multiple_of(config.bonus_life_missions)
+    {
+        campaign.lives += 1;
+    }
+
+    // Start a timer
+    commands
+        .spawn()
+        .insert(GameTimer(Timer::from_seconds(config.win_seconds, false)));
+}
+
+fn win_system(
+    mut query: Query<&mut GameTimer>,
+    current_level: Res<CurrentLevel>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // Move on to the next level after timer reaches 0, or to the summary once the campaign is
+    // complete
+    if let Ok(mut timer) = query.get_single_mut() {
+        if timer
+            .0
+            .tick(Duration::from_secs_f64(TICK_SECONDS))
+            .just_finished()
+        {
+            if current_level.0.and_then(|level| level.next()).is_some() {
+                game_state
+                    .set(GameState::Briefing)
+                    .expect("Error: Failed to set Briefing state");
+            } else {
+                game_state
+                    .set(GameState::CampaignComplete)
+                    .expect("Error: Failed to set CampaignComplete state");
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// The best campaign scores, kept in a file in the user's data directory so they last between runs.
+//
+// The file is only ever read and written by the game, but it is validated on load anyway, so a
+// corrupted or hand edited file loses the bad entries instead of crashing the game.
+use crate::{
+    flow::{Campaign, GameState},
+    level::CurrentLevel,
+    save::{user_data_path, write_ron},
+    simulation::{SimulationStage, TickStage},
+    stats::CampaignStats,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// Number of scores kept in the table
+pub const MAX_HIGH_SCORES: usize = 5;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+// Name of the file in the game's data directory
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let path = user_data_path(HIGH_SCORES_FILE);
+        let high_scores = match HighScores::load(&path) {
+            Ok(high_scores) => high_scores,
+            Err(error) if error.kind() == ErrorKind::NotFound => HighScores::default(),
+            Err(error) => {
+                warn!("Failed to load high scores {}: {}", path.display(), error);
+                HighScores::default()
+            }
+        };
+
+        app.insert_resource(high_scores)
+            .insert_resource(HighScoreFile(path))
+            .init_resource::<PendingHighScore>()
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::GameOver)
+                            .with_system(pending_high_score_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::CampaignComplete)
+                            .with_system(pending_high_score_system),
+                    )
+            });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    // Number of the furthest mission reached in the campaign
+    pub missions: u32,
+    // Day the score was set on, as YYYY-MM-DD
+    pub date: String,
+}
+
+impl HighScore {
+    fn is_valid(&self) -> bool {
+        valid_name(&self.name) && valid_date(&self.date)
+    }
+}
+
+// Scores from best to worst
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // Reads the table from a file, dropping any entries that are not valid
+    pub fn load(path: &Path) -> io::Result<HighScores> {
+        let text = fs::read_to_string(path)?;
+        let high_scores: HighScores = ron::from_str(&text)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error.to_string()))?;
+        Ok(high_scores.validated())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_ron(self, path)
+    }
+
+    pub fn validated(mut self) -> HighScores {
+        self.entries.retain(HighScore::is_valid);
+        self.entries.sort_by_key(|entry| Reverse(entry.score));
+        self.entries.truncate(MAX_HIGH_SCORES);
+        self
+    }
+
+    // Whether the score would make it into the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // Adds the score to the table, returning its position, or None if it did not make it in. Ties
+    // go to the score that was set first.
+    pub fn insert(&mut self, high_score: HighScore) -> Option<usize> {
+        if !self.qualifies(high_score.score) {
+            return None;
+        }
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| high_score.score > entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(index)
+    }
+}
+
+// File the high scores are saved to
+pub struct HighScoreFile(pub PathBuf);
+
+// Score of the campaign that just ended, waiting for the player to enter their name
+#[derive(Default)]
+pub struct PendingHighScore(pub Option<HighScore>);
+
+// Names are short and made of letters and digits only, so they fit the table and can be typed
+// without clashing with the menu keys
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+// helper function to check a date is written as YYYY-MM-DD
+fn valid_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(parts.iter())
+            .all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+// helper function to get today's date in UTC as YYYY-MM-DD
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86400) as i64;
+
+    // Convert days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn pending_high_score_system(
+    campaign: Res<Campaign>,
+    campaign_stats: Res<CampaignStats>,
+    current_level: Res<CurrentLevel>,
+    high_scores: Res<HighScores>,
+    mut pending: ResMut<PendingHighScore>,
+) {
+    // There is no current level once the whole campaign is complete, and every mission in it was
+    // cleared
+    let missions = current_level
+        .0
+        .map_or(campaign.missions_cleared, |level| level.number());
+    let score = campaign_stats.0.score;
+    pending.0 = if high_scores.qualifies(score) {
+        Some(HighScore {
+            name: String::new(),
+            score,
+            missions,
+            date: today(),
+        })
+    } else {
+        None
+    };
+}
@@ -0,0 +1,233 @@
+// The missions of the campaign, how their tanks and walls are laid out, and the order they are
+// played in.
+use crate::{
+    ai::{BrownTank, Enemy, EnemyKind},
+    collision::{Collider, Hitbox, Wall},
+    combat::{Bullet, BulletOwner, Projectile, RicochetCount, RicochetLimit},
+    config::GameConfig,
+    flow::GameState,
+    player::{Player, PlayerInput},
+    simulation::{Position, PreviousPosition, SimulationStage, TickStage},
+};
+use bevy::prelude::*;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.stage(SimulationStage, |schedule: &mut Schedule| {
+            schedule
+                .add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_enter(GameState::Briefing).with_system(setup),
+                )
+                .add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_exit(GameState::Win).with_system(next_level_system),
+                )
+        });
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Level {
+    L1,
+    L2,
+}
+
+// Where the tanks and walls of a level start out
+pub struct LevelLayout {
+    pub player: Vec2,
+    pub walls: Vec<Vec2>,
+    pub brown_tanks: Vec<Vec2>,
+}
+
+impl Level {
+    // Every level in the order they are played in the campaign
+    pub const ALL: [Level; 2] = [Level::L1, Level::L2];
+
+    // Number identifying the level in saved files
+    pub fn id(&self) -> u8 {
+        match self {
+            Level::L1 => 1,
+            Level::L2 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Level> {
+        match id {
+            1 => Some(Level::L1),
+            2 => Some(Level::L2),
+            _ => None,
+        }
+    }
+
+    // Position of the level in the campaign as shown to the player, which unlike the id may change
+    // as levels are added
+    pub fn number(&self) -> u32 {
+        match self {
+            Level::L1 => 1,
+            Level::L2 => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::L1 => "First Contact",
+            Level::L2 => "Crossfire",
+        }
+    }
+
+    pub fn layout(&self) -> LevelLayout {
+        match self {
+            Level::L1 => LevelLayout {
+                player: Vec2::new(0.0, 0.0),
+                walls: vec![
+                    Vec2::new(32.0, 64.0),
+                    Vec2::new(-32.0, 64.0),
+                    Vec2::new(32.0, -64.0),
+                ],
+                brown_tanks: vec![Vec2::new(-130.0, 150.0)],
+            },
+            Level::L2 => LevelLayout {
+                player: Vec2::new(0.0, 0.0),
+                walls: vec![
+                    Vec2::new(32.0, 64.0),
+                    Vec2::new(-32.0, 64.0),
+                    Vec2::new(32.0, -64.0),
+                    Vec2::new(-32.0, -64.0),
+                    Vec2::new(-80.0, -80.0),
+                ],
+                brown_tanks: vec![Vec2::new(-100.0, 100.0), Vec2::new(100.0, 150.0)],
+            },
+        }
+    }
+
+    // Level that follows this one in the campaign, if any
+    pub fn next(&self) -> Option<Level> {
+        match self {
+            Level::L1 => Some(Level::L2),
+            Level::L2 => None,
+        }
+    }
+}
+
+pub struct CurrentLevel(pub Option<Level>);
+
+// Image an entity is drawn with when the game is rendered
+#[derive(Component)]
+pub struct Texture(pub &'static str);
+
+// Creator for "prefabs"
+pub struct Creator<'a> {
+    pub commands: Commands<'a, 'a>,
+    pub config: &'a GameConfig,
+}
+
+impl<'a> Creator<'a> {
+    pub fn create_player(&mut self, x: f32, y: f32) -> Entity {
+        self.commands
+            .spawn()
+            .insert(Texture("player.png"))
+            .insert(Position(Vec2::new(x, y)))
+            .insert(PreviousPosition(Vec2::new(x, y)))
+            .insert(Player {
+                speed: self.config.player.speed,
+            })
+            .insert(self.config.player.weapon.clone())
+            .insert(Hitbox(self.config.player.hitbox))
+            .insert(Collider::Player)
+            .id()
+    }
+
+    pub fn create_wall(&mut self, x: f32, y: f32) -> Entity {
+        self.commands
+            .spawn()
+            .insert(Texture("wall.png"))
+            .insert(Position(Vec2::new(x, y)))
+            .insert(PreviousPosition(Vec2::new(x, y)))
+            .insert(Hitbox(Vec2::new(32.0, 32.0)))
+            .insert(Collider::Wall)
+            .insert(Wall)
+            .id()
+    }
+
+    pub fn create_brown_tank(&mut self, x: f32, y: f32) -> Entity {
+        self.commands
+            .spawn()
+            .insert(Texture("enemy_brown.png"))
+            .insert(Position(Vec2::new(x, y)))
+            .insert(PreviousPosition(Vec2::new(x, y)))
+            .insert(self.config.brown_tank.weapon.clone())
+            .insert(BrownTank)
+            .insert(Enemy)
+            .insert(EnemyKind::Brown)
+            .insert(Hitbox(self.config.brown_tank.hitbox))
+            .insert(Collider::Enemy)
+            .id()
+    }
+
+    pub fn create_projectile(
+        &mut self,
+        projectile: Projectile,
+        position: Vec2,
+        velocity: Vec2,
+        ricochets: u32,
+        owner: Entity,
+    ) -> Entity {
+        let hitbox = match projectile {
+            Projectile::Bullet => self.config.bullet_hitbox,
+        };
+        self.commands
+            .spawn()
+            .insert(Texture(projectile.texture()))
+            .insert(Bullet { velocity })
+            .insert(Position(position))
+            .insert(PreviousPosition(position))
+            .insert(BulletOwner(owner))
+            .insert(RicochetLimit(ricochets))
+            .insert(RicochetCount(0))
+            .insert(Hitbox(hitbox))
+            .insert(Collider::Bullet)
+            .id()
+    }
+}
+
+// Initial setup system
+fn setup(
+    commands: Commands,
+    config: Res<GameConfig>,
+    current_level: Res<CurrentLevel>,
+    mut player_input: ResMut<PlayerInput>,
+) {
+    // Drop any input buffered before the mission started
+    *player_input = PlayerInput::default();
+
+    let mut creator = Creator {
+        commands,
+        config: &config,
+    };
+    if let Some(level) = &current_level.0 {
+        let layout = level.layout();
+
+        // player
+        creator.create_player(layout.player.x, layout.player.y);
+
+        // create walls
+        for wall in layout.walls {
+            creator.create_wall(wall.x, wall.y);
+        }
+
+        // create enemies
+        for tank in layout.brown_tanks {
+            creator.create_brown_tank(tank.x, tank.y);
+        }
+    }
+}
+
+fn next_level_system(mut current_level: ResMut<CurrentLevel>) {
+    // Set next level to go to
+    if let Some(level) = &current_level.0 {
+        current_level.0 = level.next();
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod ai;
+pub mod collision;
+pub mod combat;
+pub mod config;
+pub mod env;
+pub mod flow;
+pub mod highscore;
+pub mod level;
+pub mod menu;
+pub mod options;
+pub mod player;
+pub mod replay;
+pub mod save;
+pub mod simulation;
+pub mod stats;
+pub mod ui;
+
+#[cfg(test)]
+mod tests;
+
+// Every plugin making up the gameplay simulation. Nothing in here depends on a window or on
+// rendering, so it runs the same in the game, headless and in tests.
+pub struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        // The simulation plugin creates the stages the other plugins add their systems to
+        group
+            .add(simulation::SimulationPlugin)
+            .add(flow::GameFlowPlugin)
+            .add(level::LevelPlugin)
+            .add(player::PlayerPlugin)
+            .add(ai::AiPlugin)
+            .add(combat::CombatPlugin)
+            .add(stats::StatsPlugin);
+    }
+}
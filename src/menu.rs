@@ -0,0 +1,747 @@
+// Menus navigable by mouse, keyboard and gamepad: the main menu the game starts in, along with the
+// high score table, and the pause menu.
+//
+// A menu is a column of text items. Screens spawn one with `spawn_menu`, tagging every item with
+// an action component of their own, and handle the `MenuActivated` events for those items.
+use crate::{
+    combat::{ARENA_HEIGHT, ARENA_WIDTH},
+    flow::{GameState, StartCampaign},
+    highscore::{valid_name, HighScoreFile, HighScores, PendingHighScore, MAX_NAME_LENGTH},
+    level::Level,
+    save::{ContinueCampaign, Progress, SavedCampaign},
+    simulation::{SimulationStage, TickStage},
+    ui::{ShowFps, UiElement},
+};
+use bevy::{app::AppExit, prelude::*, ui::FocusPolicy, window::ReceivedCharacter};
+
+const TITLE_FONT_SIZE: f32 = 80.0;
+const ITEM_FONT_SIZE: f32 = 50.0;
+
+const ITEM_COLOR: Color = Color::WHITE;
+const SELECTED_ITEM_COLOR: Color = Color::YELLOW;
+const DISABLED_ITEM_COLOR: Color = Color::GRAY;
+
+// Level thumbnails show the arena at this fraction of its size
+const THUMBNAIL_SCALE: f32 = 0.125;
+const THUMBNAIL_FONT_SIZE: f32 = 20.0;
+
+// Name given to high scores when none is entered, such as when playing with a gamepad
+const DEFAULT_NAME: &str = "PLAYER";
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuActivated>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<MainMenuPage>()
+            .init_resource::<EnteredName>()
+            .add_system(menu_navigation_system)
+            .add_system(menu_highlight_system)
+            .add_system(main_menu_action_system)
+            .add_system(main_menu_page_system)
+            .add_system(name_entry_system)
+            .add_system(pause_input_system)
+            .add_system(pause_menu_action_system)
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::MainMenu)
+                            .with_system(main_menu_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::MainMenu)
+                            .with_system(despawn_screen_system::<MainMenuScreen>),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Paused).with_system(pause_menu_setup_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Paused)
+                            .with_system(despawn_screen_system::<PauseMenuScreen>),
+                    )
+            });
+    }
+}
+
+// An item of the menu on screen
+#[derive(Component)]
+pub struct MenuItem {
+    // Position of the item in the menu, from the top
+    pub index: usize,
+    // Disabled items are shown greyed out and cannot be selected
+    pub enabled: bool,
+}
+
+// The menu item that was chosen, by clicking it or by pressing enter or the gamepad's south button
+pub struct MenuActivated(pub Entity);
+
+// Index of the selected item of the menu on screen
+#[derive(Default)]
+pub struct MenuSelection(pub usize);
+
+// helper function to spawn a full screen menu with a title above its items, returning the menu's
+// root node. Every item is given its `(label, enabled, action)`, and the whole menu is tagged with
+// `screen` so it can be despawned with `despawn_screen_system`.
+pub fn spawn_menu<S: Component, A: Component>(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    screen: S,
+    title: &str,
+    items: Vec<(String, bool, A)>,
+) -> Entity {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                // Columns are laid out from the bottom up, so reverse them to list items from the
+                // top down
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            ..Default::default()
+        })
+        .insert(FocusPolicy::Pass)
+        .insert(UiElement)
+        .insert(screen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect {
+                            bottom: Val::Px(40.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        title,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: TITLE_FONT_SIZE,
+                            color: ITEM_COLOR,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(UiElement);
+
+            for (index, (label, enabled, action)) in items.into_iter().enumerate() {
+                parent
+                    .spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: Rect::all(Val::Px(8.0)),
+                            ..Default::default()
+                        },
+                        text: Text::with_section(
+                            label,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: ITEM_FONT_SIZE,
+                                color: ITEM_COLOR,
+                            },
+                            Default::default(),
+                        ),
+                        ..Default::default()
+                    })
+                    .insert(Interaction::default())
+                    .insert(MenuItem { index, enabled })
+                    .insert(action)
+                    .insert(UiElement);
+            }
+        })
+        .id()
+}
+
+// Despawns the screen tagged with `T` along with everything on it
+pub fn despawn_screen_system<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Menu items along with whether they just appeared and whether the mouse just moved onto them or
+// clicked them
+type MenuItemQuery<'a> = (
+    Entity,
+    &'a MenuItem,
+    &'a Interaction,
+    ChangeTrackers<MenuItem>,
+    ChangeTrackers<Interaction>,
+);
+
+fn menu_navigation_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut selection: ResMut<MenuSelection>,
+    query: Query<MenuItemQuery>,
+    mut activated_events: EventWriter<MenuActivated>,
+) {
+    let mut items: Vec<_> = query.iter().collect();
+    if items.is_empty() {
+        return;
+    }
+    items.sort_by_key(|(_, item, _, _, _)| item.index);
+
+    // Start at the top whenever a new menu appears
+    if items.iter().any(|(_, _, _, added, _)| added.is_added()) {
+        selection.0 = 0;
+    }
+
+    let gamepad_pressed = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton(*gamepad, button_type)))
+    };
+    let up = keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::W])
+        || gamepad_pressed(GamepadButtonType::DPadUp);
+    let down = keyboard_input.any_just_pressed([KeyCode::Down, KeyCode::S])
+        || gamepad_pressed(GamepadButtonType::DPadDown);
+    let mut activate = keyboard_input.any_just_pressed([KeyCode::Return, KeyCode::Space])
+        || gamepad_pressed(GamepadButtonType::South);
+
+    // Move the selection to the next enabled item in the direction pressed, skipping disabled
+    // ones. Without a direction, step off a disabled item if the selection is on one.
+    let count = items.len();
+    let enabled = |index: usize| items[index].1.enabled;
+    let step = if up { count - 1 } else { 1 };
+    let mut index = selection.0.min(count - 1);
+    if up || down || !enabled(index) {
+        for _ in 0..count {
+            index = (index + step) % count;
+            if enabled(index) {
+                break;
+            }
+        }
+    }
+
+    // The mouse selects whatever it moves onto, and activates whatever it clicks
+    for (_, item, interaction, _, interaction_tracker) in items.iter() {
+        if item.enabled && interaction_tracker.is_changed() {
+            match interaction {
+                Interaction::Hovered => index = item.index,
+                Interaction::Clicked => {
+                    index = item.index;
+                    activate = true;
+                }
+                Interaction::None => {}
+            }
+        }
+    }
+
+    selection.0 = index;
+    if activate && enabled(index) {
+        activated_events.send(MenuActivated(items[index].0));
+    }
+}
+
+fn menu_highlight_system(selection: Res<MenuSelection>, mut query: Query<(&MenuItem, &mut Text)>) {
+    for (item, mut text) in query.iter_mut() {
+        text.sections[0].style.color = if !item.enabled {
+            DISABLED_ITEM_COLOR
+        } else if item.index == selection.0 {
+            SELECTED_ITEM_COLOR
+        } else {
+            ITEM_COLOR
+        };
+    }
+}
+
+// Main menu systems
+#[derive(Component)]
+struct MainMenuScreen;
+
+#[derive(Component, Clone, Copy)]
+enum MainMenuAction {
+    NewCampaign,
+    Continue,
+    LevelSelect,
+    HighScores,
+    Settings,
+    Quit,
+    ToggleFps,
+    Back,
+    // Starts a campaign at the level picked from the level select
+    PlayLevel(Level),
+    // Text that cannot be selected, such as an entry of the high score table
+    Label,
+    // Shows the name being entered for a new high score
+    NameField,
+    // Confirms the name entered for a new high score
+    Done,
+}
+
+// Page of the main menu on screen
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum MainMenuPage {
+    #[default]
+    Main,
+    Settings,
+    LevelSelect,
+    HighScores,
+    NameEntry,
+}
+
+// Name typed so far for a new high score
+#[derive(Default)]
+struct EnteredName(String);
+
+fn main_menu_setup_system(
+    mut page: ResMut<MainMenuPage>,
+    pending: Res<PendingHighScore>,
+    mut entered_name: ResMut<EnteredName>,
+) {
+    // Ask for a name first if the campaign that just ended set a high score. Setting the page,
+    // even to the same one, spawns it.
+    if pending.0.is_some() {
+        entered_name.0.clear();
+        *page = MainMenuPage::NameEntry;
+    } else {
+        *page = MainMenuPage::Main;
+    }
+}
+
+// Resources shown on the pages of the main menu
+type MainMenuContent<'a> = (
+    Res<'a, ShowFps>,
+    Res<'a, HighScores>,
+    Res<'a, SavedCampaign>,
+    Res<'a, Progress>,
+);
+
+fn main_menu_page_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    page: Res<MainMenuPage>,
+    (show_fps, high_scores, saved, progress): MainMenuContent,
+    game_state: Res<State<GameState>>,
+    screens: Query<Entity, With<MainMenuScreen>>,
+) {
+    if !page.is_changed() || *game_state.current() != GameState::MainMenu {
+        return;
+    }
+
+    for entity in screens.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (title, items) = match *page {
+        MainMenuPage::Main => (
+            "Tanks",
+            vec![
+                (
+                    "New Campaign".to_string(),
+                    true,
+                    MainMenuAction::NewCampaign,
+                ),
+                (
+                    "Continue".to_string(),
+                    saved.0.is_some(),
+                    MainMenuAction::Continue,
+                ),
+                (
+                    "Level Select".to_string(),
+                    true,
+                    MainMenuAction::LevelSelect,
+                ),
+                ("High Scores".to_string(), true, MainMenuAction::HighScores),
+                ("Settings".to_string(), true, MainMenuAction::Settings),
+                ("Quit".to_string(), true, MainMenuAction::Quit),
+            ],
+        ),
+        MainMenuPage::Settings => (
+            "Settings",
+            vec![
+                (
+                    format!("Show FPS: {}", if show_fps.0 { "On" } else { "Off" }),
+                    true,
+                    MainMenuAction::ToggleFps,
+                ),
+                ("Back".to_string(), true, MainMenuAction::Back),
+            ],
+        ),
+        MainMenuPage::LevelSelect => {
+            let mut items: Vec<_> = Level::ALL
+                .iter()
+                .map(|level| {
+                    let unlocked = progress.is_unlocked(*level);
+                    (
+                        level_label(*level, unlocked, &progress),
+                        unlocked,
+                        MainMenuAction::PlayLevel(*level),
+                    )
+                })
+                .collect();
+            items.push(("Back".to_string(), true, MainMenuAction::Back));
+            ("Level Select", items)
+        }
+        MainMenuPage::HighScores => {
+            // Scores are listed as disabled items, so only Back can be selected
+            let mut items: Vec<_> = high_scores
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    (
+                        format!(
+                            "{}. {}  {}  (mission {}, {})",
+                            index + 1,
+                            entry.name,
+                            entry.score,
+                            entry.missions,
+                            entry.date
+                        ),
+                        false,
+                        MainMenuAction::Label,
+                    )
+                })
+                .collect();
+            if items.is_empty() {
+                items.push(("No scores yet".to_string(), false, MainMenuAction::Label));
+            }
+            items.push(("Back".to_string(), true, MainMenuAction::Back));
+            ("High Scores", items)
+        }
+        MainMenuPage::NameEntry => (
+            "New High Score!",
+            vec![
+                (name_label(""), false, MainMenuAction::NameField),
+                ("Done".to_string(), true, MainMenuAction::Done),
+            ],
+        ),
+    };
+    let menu = spawn_menu(&mut commands, &asset_server, MainMenuScreen, title, items);
+
+    // Show a thumbnail of every level below the level select
+    if *page == MainMenuPage::LevelSelect {
+        let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+        commands.entity(menu).with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: Rect {
+                            top: Val::Px(30.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass)
+                .insert(UiElement)
+                .with_children(|row| {
+                    for level in Level::ALL {
+                        spawn_thumbnail(row, level, progress.is_unlocked(level), font.clone());
+                    }
+                });
+        });
+    }
+}
+
+// helper function to label a level in the level select with its best results so far
+fn level_label(level: Level, unlocked: bool, progress: &Progress) -> String {
+    if !unlocked {
+        return format!("Mission {}: Locked", level.number());
+    }
+    match progress.record(level) {
+        Some(record) => format!(
+            "Mission {}: {}  (best {:.1}s, {})",
+            level.number(),
+            level.name(),
+            record.best_seconds,
+            record.best_score
+        ),
+        None => format!("Mission {}: {}", level.number(), level.name()),
+    }
+}
+
+// helper function to draw a miniature of a level's layout, with its number below it. Locked
+// levels are drawn dimmed.
+fn spawn_thumbnail(parent: &mut ChildBuilder, level: Level, unlocked: bool, font: Handle<Font>) {
+    let alpha = if unlocked { 1.0 } else { 0.3 };
+    let layout = level.layout();
+    let mut pieces = vec![(layout.player, Color::rgba(0.2, 0.4, 0.9, alpha))];
+    pieces.extend(
+        layout
+            .walls
+            .iter()
+            .map(|wall| (*wall, Color::rgba(0.6, 0.6, 0.6, alpha))),
+    );
+    pieces.extend(
+        layout
+            .brown_tanks
+            .iter()
+            .map(|tank| (*tank, Color::rgba(0.6, 0.4, 0.2, alpha))),
+    );
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                margin: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(FocusPolicy::Pass)
+        .insert(UiElement)
+        .with_children(|column| {
+            column
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(
+                            Val::Px(ARENA_WIDTH * THUMBNAIL_SCALE),
+                            Val::Px(ARENA_HEIGHT * THUMBNAIL_SCALE),
+                        ),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.1, 0.2, 0.1, alpha).into(),
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass)
+                .insert(UiElement)
+                .with_children(|arena| {
+                    // Tanks and walls are all 32 units across. The arena's origin is at its
+                    // center, while UI positions start from the bottom left corner.
+                    let size = 32.0 * THUMBNAIL_SCALE;
+                    for (position, color) in pieces {
+                        arena
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: Rect {
+                                        left: Val::Px(
+                                            (position.x + ARENA_WIDTH / 2.0) * THUMBNAIL_SCALE
+                                                - size / 2.0,
+                                        ),
+                                        bottom: Val::Px(
+                                            (position.y + ARENA_HEIGHT / 2.0) * THUMBNAIL_SCALE
+                                                - size / 2.0,
+                                        ),
+                                        ..Default::default()
+                                    },
+                                    size: Size::new(Val::Px(size), Val::Px(size)),
+                                    ..Default::default()
+                                },
+                                color: color.into(),
+                                ..Default::default()
+                            })
+                            .insert(FocusPolicy::Pass)
+                            .insert(UiElement);
+                    }
+                });
+
+            column
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        format!("Mission {}", level.number()),
+                        TextStyle {
+                            font,
+                            font_size: THUMBNAIL_FONT_SIZE,
+                            color: Color::rgba(1.0, 1.0, 1.0, alpha),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(UiElement);
+        });
+}
+
+// helper function to label the name being entered, with a cursor at the end
+fn name_label(name: &str) -> String {
+    format!("Name: {}_", name)
+}
+
+fn name_entry_system(
+    page: Res<MainMenuPage>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut character_events: EventReader<ReceivedCharacter>,
+    mut entered_name: ResMut<EnteredName>,
+    mut query: Query<(&MainMenuAction, &mut Text)>,
+) {
+    if *page != MainMenuPage::NameEntry {
+        // Drop anything typed elsewhere
+        character_events.iter().count();
+        return;
+    }
+
+    let mut changed = false;
+    for event in character_events.iter() {
+        if event.char.is_ascii_alphanumeric() && entered_name.0.len() < MAX_NAME_LENGTH {
+            entered_name.0.push(event.char.to_ascii_uppercase());
+            changed = true;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        changed |= entered_name.0.pop().is_some();
+    }
+
+    if changed {
+        for (action, mut text) in query.iter_mut() {
+            if let MainMenuAction::NameField = action {
+                text.sections[0].value = name_label(&entered_name.0);
+            }
+        }
+    }
+}
+
+// Resources the main menu acts on, apart from its own pages
+type MainMenuResources<'a> = (
+    ResMut<'a, ShowFps>,
+    ResMut<'a, PendingHighScore>,
+    ResMut<'a, HighScores>,
+    Res<'a, HighScoreFile>,
+    Res<'a, EnteredName>,
+);
+
+fn main_menu_action_system(
+    mut activated_events: EventReader<MenuActivated>,
+    actions: Query<&MainMenuAction>,
+    mut page: ResMut<MainMenuPage>,
+    (mut show_fps, mut pending, mut high_scores, high_score_file, entered_name): MainMenuResources,
+    mut start_campaign_events: EventWriter<StartCampaign>,
+    mut continue_campaign_events: EventWriter<ContinueCampaign>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    for event in activated_events.iter() {
+        match actions.get(event.0) {
+            Ok(MainMenuAction::NewCampaign) => {
+                start_campaign_events.send(StartCampaign { level: Level::L1 })
+            }
+            Ok(MainMenuAction::Continue) => continue_campaign_events.send(ContinueCampaign),
+            Ok(MainMenuAction::LevelSelect) => *page = MainMenuPage::LevelSelect,
+            Ok(MainMenuAction::PlayLevel(level)) => {
+                start_campaign_events.send(StartCampaign { level: *level })
+            }
+            Ok(MainMenuAction::HighScores) => *page = MainMenuPage::HighScores,
+            Ok(MainMenuAction::Settings) => *page = MainMenuPage::Settings,
+            Ok(MainMenuAction::Quit) => app_exit_events.send(AppExit),
+            Ok(MainMenuAction::ToggleFps) => {
+                show_fps.0 = !show_fps.0;
+                // Spawn the page again to update the label
+                *page = MainMenuPage::Settings;
+            }
+            Ok(MainMenuAction::Back) => *page = MainMenuPage::Main,
+            Ok(MainMenuAction::Label | MainMenuAction::NameField) => {}
+            Ok(MainMenuAction::Done) => {
+                if let Some(mut high_score) = pending.0.take() {
+                    high_score.name = if valid_name(&entered_name.0) {
+                        entered_name.0.clone()
+                    } else {
+                        DEFAULT_NAME.to_string()
+                    };
+                    high_scores.insert(high_score);
+                    if let Err(error) = high_scores.save(&high_score_file.0) {
+                        warn!(
+                            "Failed to save high scores {}: {}",
+                            high_score_file.0.display(),
+                            error
+                        );
+                    }
+                }
+                *page = MainMenuPage::HighScores;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+// Pause menu systems
+#[derive(Component)]
+struct PauseMenuScreen;
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuAction {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+fn pause_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // Escape or the gamepad's start button pauses a mission in progress, and resumes it again
+    let pressed = keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|gamepad| {
+            gamepad_input.just_pressed(GamepadButton(*gamepad, GamepadButtonType::Start))
+        });
+    if !pressed {
+        return;
+    }
+
+    match game_state.current() {
+        GameState::Playing => game_state
+            .overwrite_push(GameState::Paused)
+            .expect("Error: Failed to push Paused state"),
+        GameState::Paused => game_state
+            .overwrite_pop()
+            .expect("Error: Failed to pop Paused state"),
+        _ => {}
+    }
+}
+
+fn pause_menu_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        PauseMenuScreen,
+        "Paused",
+        vec![
+            ("Resume".to_string(), true, PauseMenuAction::Resume),
+            (
+                "Restart Mission".to_string(),
+                true,
+                PauseMenuAction::Restart,
+            ),
+            (
+                "Quit to Menu".to_string(),
+                true,
+                PauseMenuAction::QuitToMenu,
+            ),
+        ],
+    );
+}
+
+fn pause_menu_action_system(
+    mut activated_events: EventReader<MenuActivated>,
+    actions: Query<&PauseMenuAction>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for event in activated_events.iter() {
+        if *game_state.current() != GameState::Paused {
+            continue;
+        }
+
+        match actions.get(event.0) {
+            Ok(PauseMenuAction::Resume) => game_state
+                .overwrite_pop()
+                .expect("Error: Failed to pop Paused state"),
+            // Leaving the mission below the pause menu tears it down
+            Ok(PauseMenuAction::Restart) => game_state
+                .overwrite_replace(GameState::Restarting)
+                .expect("Error: Failed to replace state with Restarting"),
+            Ok(PauseMenuAction::QuitToMenu) => game_state
+                .overwrite_replace(GameState::MainMenu)
+                .expect("Error: Failed to replace state with MainMenu"),
+            Err(_) => {}
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Options the game is started with, read from the command line.
+use crate::level::Level;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: tanks [--level <number>] [--seed <seed>] \
+                         [--windowed <width>x<height>] [--replay <file>] [--headless] \
+                         [--ticks <count>] [--no-fps]";
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    // Level to start the campaign at, with `--level <number>`, skipping the main menu
+    pub level: Option<Level>,
+    // Seed of the simulation, with `--seed <seed>`
+    pub seed: u64,
+    // Size of the window, with `--windowed <width>x<height>`
+    pub window_size: Option<(f32, f32)>,
+    // Replay file to play back, with `--replay <file>`. The level and seed of the replay take
+    // precedence over `--level` and `--seed`.
+    pub replay: Option<PathBuf>,
+    // Run the simulation without a window, with `--headless`
+    pub headless: bool,
+    // Number of ticks after which a headless run stops, with `--ticks <count>`
+    pub ticks: Option<u64>,
+    // Show the frame rate if the config says so, unless hidden with `--no-fps`
+    pub show_fps: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            level: None,
+            seed: 0,
+            window_size: None,
+            replay: None,
+            headless: false,
+            ticks: None,
+            show_fps: true,
+        }
+    }
+}
+
+impl Options {
+    // Reads the options from the arguments after the program name, failing on any argument or
+    // value it does not understand
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--level" => {
+                    let id = value(&mut args, &arg)?;
+                    options.level = Some(
+                        id.parse()
+                            .ok()
+                            .and_then(Level::from_id)
+                            .ok_or_else(|| format!("Unknown level {}", id))?,
+                    );
+                }
+                "--seed" => {
+                    let seed = value(&mut args, &arg)?;
+                    options.seed = seed.parse().map_err(|_| format!("Invalid seed {}", seed))?;
+                }
+                "--windowed" => {
+                    let size = value(&mut args, &arg)?;
+                    options.window_size = Some(parse_window_size(&size).ok_or_else(|| {
+                        format!("Invalid window size {}, expected <width>x<height>", size)
+                    })?);
+                }
+                "--replay" => options.replay = Some(PathBuf::from(value(&mut args, &arg)?)),
+                "--headless" => options.headless = true,
+                "--ticks" => {
+                    let ticks = value(&mut args, &arg)?;
+                    options.ticks = Some(
+                        ticks
+                            .parse()
+                            .map_err(|_| format!("Invalid tick count {}", ticks))?,
+                    );
+                }
+                "--no-fps" => options.show_fps = false,
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+// helper function to take the value following an option
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", option))
+}
+
+// helper function to parse a window size in whole pixels, such as 1280x720
+fn parse_window_size(size: &str) -> Option<(f32, f32)> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height): (u32, u32) = (width.parse().ok()?, height.parse().ok()?);
+    if width > 0 && height > 0 {
+        Some((width as f32, height as f32))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,189 @@
+// The player's tank and the keyboard and mouse controls driving it.
+use crate::{
+    collision::{bounds, Collider, ColliderIndex, Hitbox, WallGeometry},
+    combat::{arena_units_per_pixel, FireIntent},
+    flow::GameState,
+    simulation::{Position, SimulationStage, SimulationSystem, TickStage, TICK_SECONDS},
+};
+use bevy::{
+    prelude::*,
+    sprite::collide_aabb::{collide, Collision},
+};
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CursorPosition { pos: Vec2::ZERO })
+            .init_resource::<PlayerInput>()
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule.add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_update(GameState::Playing)
+                        .with_system(
+                            player_shoot_system
+                                .label(SimulationSystem::Input)
+                                .after(SimulationSystem::Prepare),
+                        )
+                        .with_system(
+                            player_movement_system
+                                .label(SimulationSystem::Movement)
+                                .after(SimulationSystem::Firing),
+                        ),
+                )
+            });
+    }
+}
+
+// Reads the player's input from the keyboard and mouse of the game window
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(cursor_position_system)
+            .add_system(player_input_system);
+    }
+}
+
+pub struct CursorPosition {
+    pub pos: Vec2,
+}
+
+// Player controls gathered every frame, waiting to be consumed by the next simulation tick
+#[derive(Default)]
+pub struct PlayerInput {
+    pub direction: Vec2,
+    pub fire: bool,
+}
+
+#[derive(Component)]
+pub struct Player {
+    pub speed: f32,
+}
+
+fn player_movement_system(
+    player_input: Res<PlayerInput>,
+    wall_geometry: Res<WallGeometry>,
+    collider_index: Res<ColliderIndex>,
+    mut query: Query<(&Player, &mut Position, &Hitbox)>,
+) {
+    if let Ok((player, mut player_position, player_hitbox)) = query.get_single_mut() {
+        let mut collisions: Vec<Collision> = vec![];
+        let (min, max) = bounds(player_position.0, player_hitbox.0);
+
+        // Stop player movement on collision with walls or enemies
+        for rect in wall_geometry.query(min, max) {
+            let collision = collide(
+                player_position.0.extend(0.0),
+                player_hitbox.0,
+                rect.center().extend(0.0),
+                rect.size(),
+            );
+            collisions.extend(collision);
+        }
+
+        for entry in collider_index.query(min, max) {
+            if entry.collider == Collider::Enemy {
+                let collision = collide(
+                    player_position.0.extend(0.0),
+                    player_hitbox.0,
+                    entry.position.extend(0.0),
+                    entry.size,
+                );
+                collisions.extend(collision);
+            }
+        }
+
+        // Normalize the direction so player doesn't move faster on diagonals
+        let normalized_direction = player_input.direction.try_normalize().unwrap_or(Vec2::ZERO);
+        let translation = &mut player_position.0;
+
+        let mut stop_x = false;
+        let mut stop_y = false;
+
+        for collision in collisions {
+            match collision {
+                Collision::Left => stop_x = normalized_direction.x > 0.0,
+                Collision::Right => stop_x = normalized_direction.x < 0.0,
+                Collision::Top => stop_y = normalized_direction.y < 0.0,
+                Collision::Bottom => stop_y = normalized_direction.y > 0.0,
+            }
+        }
+
+        if !stop_x {
+            translation.x += TICK_SECONDS as f32 * normalized_direction.x * player.speed;
+        }
+
+        if !stop_y {
+            translation.y += TICK_SECONDS as f32 * normalized_direction.y * player.speed;
+        }
+    }
+}
+
+fn player_shoot_system(
+    mut player_input: ResMut<PlayerInput>,
+    query: Query<(Entity, &Position), With<Player>>,
+    cursor_position: Res<CursorPosition>,
+    mut fire_intents: EventWriter<FireIntent>,
+) {
+    let fire = std::mem::take(&mut player_input.fire);
+    if let Ok((player_entity, player_position)) = query.get_single() {
+        if fire {
+            // Fire towards the cursor
+            fire_intents.send(FireIntent {
+                tank: player_entity,
+                direction: cursor_position.pos - player_position.0,
+            });
+        }
+    }
+}
+
+// Input systems
+fn cursor_position_system(windows: Res<Windows>, mut cursor_position: ResMut<CursorPosition>) {
+    if let Some(cursor_pos) = calculate_cursor_position(windows) {
+        cursor_position.pos = cursor_pos;
+    }
+}
+
+// helper function to get cursor position
+fn calculate_cursor_position(windows: Res<Windows>) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor_position = window.cursor_position()?;
+    let scale = arena_units_per_pixel(window.width(), window.height());
+    Some(Vec2::new(
+        (cursor_position.x - window.width() / 2.0) * scale,
+        (cursor_position.y - window.height() / 2.0) * scale,
+    ))
+}
+
+fn player_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    game_state: Res<State<GameState>>,
+    mut player_input: ResMut<PlayerInput>,
+) {
+    let mut direction: Vec2 = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::A) {
+        direction -= Vec2::X;
+    }
+
+    if keyboard_input.pressed(KeyCode::D) {
+        direction += Vec2::X;
+    }
+
+    if keyboard_input.pressed(KeyCode::W) {
+        direction += Vec2::Y;
+    }
+
+    if keyboard_input.pressed(KeyCode::S) {
+        direction -= Vec2::Y;
+    }
+
+    player_input.direction = direction;
+
+    // Keep the shot buffered until a tick has consumed it, so clicks are never lost or repeated
+    // no matter how many ticks run this frame. Clicks on menus are not shots.
+    if mouse_input.just_pressed(MouseButton::Left) && *game_state.current() == GameState::Playing {
+        player_input.fire = true;
+    }
+}
@@ -0,0 +1,345 @@
+// Recording and playback of the player's input, one file per mission.
+//
+// A replay file starts with a header holding the level and the simulation seed, followed by one
+// record per simulation tick. Each record is a single flags byte, followed by the cursor position
+// only on ticks where the cursor moved.
+use crate::{
+    flow::{GameState, MissionEnded},
+    level::{CurrentLevel, Level},
+    player::{CursorPosition, PlayerInput},
+    save::user_data_path,
+    simulation::{SimulationSeed, SimulationStage, SimulationSystem, TickStage},
+};
+use bevy::prelude::*;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: &[u8; 4] = b"TNKR";
+const VERSION: u8 = 1;
+
+// Name of the directory in the game's data directory new recordings are written to
+const REPLAY_DIRECTORY: &str = "replays";
+
+// Bits of the flags byte written for every tick
+const LEFT: u8 = 1 << 0;
+const RIGHT: u8 = 1 << 1;
+const UP: u8 = 1 << 2;
+const DOWN: u8 = 1 << 3;
+const FIRE: u8 = 1 << 4;
+const CURSOR_MOVED: u8 = 1 << 5;
+
+// Saves the player's input of every mission to a new file in the replay directory
+pub struct ReplayRecordingPlugin;
+
+impl Plugin for ReplayRecordingPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<ReplayDirectory>() {
+            app.insert_resource(ReplayDirectory(user_data_path(REPLAY_DIRECTORY)));
+        }
+
+        app.init_resource::<ReplayRecorder>()
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Playing).with_system(replay_start_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Playing)
+                            .with_system(replay_record_system.label(SimulationSystem::Prepare)),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_exit(GameState::Playing).with_system(replay_finish_system),
+                    )
+            });
+    }
+}
+
+// Feeds the input of the `ReplayPlayback` resource to the simulation, returning to the main menu
+// once the recorded mission ends
+pub struct ReplayPlaybackPlugin;
+
+impl Plugin for ReplayPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(replay_end_system)
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule.add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_update(GameState::Playing)
+                        .with_system(replay_playback_system.label(SimulationSystem::Prepare)),
+                )
+            });
+    }
+}
+
+// Directory new recordings are written to
+pub struct ReplayDirectory(pub PathBuf);
+
+// Everything the simulation reads from the player in a single tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickInput {
+    pub direction: Vec2,
+    pub cursor: Vec2,
+    pub fire: bool,
+}
+
+pub struct Replay {
+    pub level: Level,
+    pub seed: u64,
+    pub ticks: Vec<TickInput>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u8(&mut reader)? != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "not a supported replay file",
+            ));
+        }
+
+        let level = Level::from_id(read_u8(&mut reader)?)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "unknown level"))?;
+        let mut seed = [0; 8];
+        reader.read_exact(&mut seed)?;
+
+        let mut ticks = vec![];
+        let mut cursor = Vec2::ZERO;
+        loop {
+            let flags = match read_u8(&mut reader) {
+                Ok(flags) => flags,
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => break,
+                Err(error) => return Err(error),
+            };
+
+            if flags & CURSOR_MOVED != 0 {
+                cursor = Vec2::new(read_f32(&mut reader)?, read_f32(&mut reader)?);
+            }
+
+            ticks.push(decode_tick(flags, cursor));
+        }
+
+        Ok(Replay {
+            level,
+            seed: u64::from_le_bytes(seed),
+            ticks,
+        })
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn encode_flags(input: &TickInput) -> u8 {
+    let mut flags = 0;
+    if input.direction.x < 0.0 {
+        flags |= LEFT;
+    }
+
+    if input.direction.x > 0.0 {
+        flags |= RIGHT;
+    }
+
+    if input.direction.y > 0.0 {
+        flags |= UP;
+    }
+
+    if input.direction.y < 0.0 {
+        flags |= DOWN;
+    }
+
+    if input.fire {
+        flags |= FIRE;
+    }
+
+    flags
+}
+
+fn decode_tick(flags: u8, cursor: Vec2) -> TickInput {
+    let mut direction = Vec2::ZERO;
+    if flags & LEFT != 0 {
+        direction -= Vec2::X;
+    }
+
+    if flags & RIGHT != 0 {
+        direction += Vec2::X;
+    }
+
+    if flags & UP != 0 {
+        direction += Vec2::Y;
+    }
+
+    if flags & DOWN != 0 {
+        direction -= Vec2::Y;
+    }
+
+    TickInput {
+        direction,
+        cursor,
+        fire: flags & FIRE != 0,
+    }
+}
+
+// Writes the input of the mission in progress to a replay file, tick by tick
+#[derive(Default)]
+pub struct ReplayRecorder {
+    writer: Option<BufWriter<File>>,
+    cursor: Vec2,
+}
+
+impl ReplayRecorder {
+    fn start(&mut self, directory: &Path, level: &Level, seed: u64) -> io::Result<PathBuf> {
+        fs::create_dir_all(directory)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0);
+        let path = directory.join(format!("mission{}-{}.rpl", level.id(), timestamp));
+        self.start_file(&path, level, seed)?;
+        Ok(path)
+    }
+
+    // Starts recording a mission to the given file, replacing anything in it
+    pub fn start_file(&mut self, path: &Path, level: &Level, seed: u64) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, level.id()])?;
+        writer.write_all(&seed.to_le_bytes())?;
+
+        self.writer = Some(writer);
+        self.cursor = Vec2::ZERO;
+        Ok(())
+    }
+
+    pub fn record(&mut self, input: &TickInput) -> io::Result<()> {
+        if let Some(writer) = &mut self.writer {
+            let mut flags = encode_flags(input);
+            if input.cursor != self.cursor {
+                flags |= CURSOR_MOVED;
+            }
+
+            writer.write_all(&[flags])?;
+            if flags & CURSOR_MOVED != 0 {
+                writer.write_all(&input.cursor.x.to_le_bytes())?;
+                writer.write_all(&input.cursor.y.to_le_bytes())?;
+                self.cursor = input.cursor;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.writer.take() {
+            Some(mut writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// Input of a loaded replay, fed to the simulation instead of the keyboard and mouse
+pub struct ReplayPlayback {
+    ticks: Vec<TickInput>,
+    next: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback {
+            ticks: replay.ticks,
+            next: 0,
+        }
+    }
+}
+
+fn replay_start_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    directory: Res<ReplayDirectory>,
+    current_level: Res<CurrentLevel>,
+    seed: Res<SimulationSeed>,
+) {
+    if let Some(level) = &current_level.0 {
+        match recorder.start(&directory.0, level, seed.0) {
+            Ok(path) => info!("Recording replay to {}", path.display()),
+            Err(error) => warn!("Failed to start recording replay: {}", error),
+        }
+    }
+}
+
+fn replay_record_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    player_input: Res<PlayerInput>,
+    cursor_position: Res<CursorPosition>,
+) {
+    let input = TickInput {
+        direction: player_input.direction,
+        cursor: cursor_position.pos,
+        fire: player_input.fire,
+    };
+
+    if let Err(error) = recorder.record(&input) {
+        warn!("Failed to record replay, recording stopped: {}", error);
+        recorder.writer = None;
+    }
+}
+
+fn replay_finish_system(mut recorder: ResMut<ReplayRecorder>) {
+    if let Err(error) = recorder.finish() {
+        warn!("Failed to save replay: {}", error);
+    }
+}
+
+fn replay_playback_system(
+    mut playback: ResMut<ReplayPlayback>,
+    mut player_input: ResMut<PlayerInput>,
+    mut cursor_position: ResMut<CursorPosition>,
+) {
+    // Once the recording runs out the player simply stops
+    let input = match playback.ticks.get(playback.next) {
+        Some(input) => *input,
+        None => {
+            *player_input = PlayerInput::default();
+            return;
+        }
+    };
+
+    playback.next += 1;
+    player_input.direction = input.direction;
+    player_input.fire = input.fire;
+    cursor_position.pos = input.cursor;
+}
+
+fn replay_end_system(
+    mut mission_ended_events: EventReader<MissionEnded>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    // A replay holds a single mission, so there is nothing left to play once it ends
+    if let Some(event) = mission_ended_events.iter().last() {
+        info!(
+            "Replay finished, mission {}",
+            if event.won { "won" } else { "lost" }
+        );
+        game_state
+            .overwrite_set(GameState::MainMenu)
+            .expect("Error: Failed to set MainMenu state");
+    }
+}
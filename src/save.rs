@@ -0,0 +1,401 @@
+// Progress kept between runs of the game: the campaign saved at the start of every mission, so it
+// can be continued after the game is closed, and the levels unlocked along with the best results
+// in each of them.
+//
+// Save files carry a version number. When the format changes, the version goes up and the old
+// format is kept as a struct of its own that upgrades to the next version, so saves of any version
+// are brought up to date one version at a time and players never lose their progress.
+use crate::{
+    flow::{Campaign, GameState},
+    level::{CurrentLevel, Level},
+    simulation::{SimulationSeed, SimulationStage, TickStage},
+    stats::{CampaignStats, MissionStats, Stats},
+};
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+// Version of the save format written by this build
+pub const SAVE_VERSION: u32 = 2;
+// Version of the progress format written by this build
+pub const PROGRESS_VERSION: u32 = 1;
+
+// Names of the files in the game's data directory
+const SAVE_FILE: &str = "campaign.ron";
+const PROGRESS_FILE: &str = "progress.ron";
+
+pub struct SavePlugin {
+    // Whether to save progress as missions start, which replays should not do
+    pub autosave: bool,
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let path = user_data_path(SAVE_FILE);
+        let save = match CampaignSave::load(&path) {
+            Ok(save) => Some(save),
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => {
+                warn!("Failed to load save {}: {}", path.display(), error);
+                None
+            }
+        };
+
+        let progress_path = user_data_path(PROGRESS_FILE);
+        let progress = match Progress::load(&progress_path) {
+            Ok(progress) => progress,
+            Err(error) if error.kind() == ErrorKind::NotFound => Progress::default(),
+            Err(error) => {
+                warn!(
+                    "Failed to load progress {}: {}",
+                    progress_path.display(),
+                    error
+                );
+                Progress::default()
+            }
+        };
+
+        app.insert_resource(SavedCampaign(save))
+            .insert_resource(SaveFile(path))
+            .insert_resource(progress)
+            .insert_resource(ProgressFile(progress_path))
+            .add_event::<ContinueCampaign>()
+            .add_system(continue_campaign_system);
+
+        if self.autosave {
+            app.stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Briefing)
+                            .with_system(save_system)
+                            .with_system(unlock_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Win).with_system(level_record_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::GameOver).with_system(delete_save_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::CampaignComplete)
+                            .with_system(delete_save_system),
+                    )
+            });
+        }
+    }
+}
+
+// Progress through a campaign as it was when a mission started
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CampaignSave {
+    pub version: u32,
+    // Id of the mission to continue from
+    pub level: u8,
+    pub lives: u32,
+    pub missions_cleared: u32,
+    pub stats: Stats,
+    // Seed of the simulation the campaign was played with
+    pub seed: u64,
+}
+
+// Campaign saves of version 1, which did not keep the seed
+#[derive(Deserialize)]
+struct CampaignSaveV1 {
+    level: u8,
+    lives: u32,
+    missions_cleared: u32,
+    stats: Stats,
+}
+
+impl CampaignSaveV1 {
+    // Upgrades to the next version, which is the current one. The seed was not saved, so continue
+    // with the default one.
+    fn upgrade(self) -> CampaignSave {
+        CampaignSave {
+            version: SAVE_VERSION,
+            level: self.level,
+            lives: self.lives,
+            missions_cleared: self.missions_cleared,
+            stats: self.stats,
+            seed: 0,
+        }
+    }
+}
+
+// Just the version of a save, read before the rest to know how to read it
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+impl CampaignSave {
+    pub fn load(path: &Path) -> io::Result<CampaignSave> {
+        let text = fs::read_to_string(path)?;
+        let save = migrate(&text).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+        if Level::from_id(save.level).is_none() {
+            return Err(io::Error::new(ErrorKind::InvalidData, "unknown level"));
+        }
+        Ok(save)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_ron(self, path)
+    }
+}
+
+// helper function to read a save of any known version, bringing it up to the current one
+fn migrate(text: &str) -> Result<CampaignSave, String> {
+    let SaveVersion { version } = parse(text)?;
+    match version {
+        1 => parse::<CampaignSaveV1>(text).map(CampaignSaveV1::upgrade),
+        SAVE_VERSION => parse(text),
+        _ => Err(format!("unsupported save version {}", version)),
+    }
+}
+
+// helper function to read a RON value, keeping the error as text
+fn parse<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    ron::from_str(text).map_err(|error| error.to_string())
+}
+
+// Best results the player has had in a level
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub level: u8,
+    pub best_seconds: f64,
+    pub best_score: u32,
+}
+
+// Levels the player may pick from the level select, and their records
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub version: u32,
+    // Ids of the levels unlocked, on top of the first one which always is
+    pub unlocked: Vec<u8>,
+    pub records: Vec<LevelRecord>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            version: PROGRESS_VERSION,
+            unlocked: vec![],
+            records: vec![],
+        }
+    }
+}
+
+impl Progress {
+    // Reads the progress from a file, dropping anything about levels that do not exist
+    pub fn load(path: &Path) -> io::Result<Progress> {
+        let text = fs::read_to_string(path)?;
+        let mut progress = migrate_progress(&text)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+        progress.unlocked.retain(|id| Level::from_id(*id).is_some());
+        progress
+            .records
+            .retain(|record| Level::from_id(record.level).is_some());
+        Ok(progress)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_ron(self, path)
+    }
+
+    pub fn is_unlocked(&self, level: Level) -> bool {
+        level == Level::ALL[0] || self.unlocked.contains(&level.id())
+    }
+
+    // Unlocks the level, returning whether it was locked before
+    pub fn unlock(&mut self, level: Level) -> bool {
+        if self.is_unlocked(level) {
+            return false;
+        }
+        self.unlocked.push(level.id());
+        true
+    }
+
+    pub fn record(&self, level: Level) -> Option<&LevelRecord> {
+        self.records
+            .iter()
+            .find(|record| record.level == level.id())
+    }
+
+    // Keeps the time and score of a won mission if they beat the level's records, and unlocks the
+    // level after it
+    pub fn record_win(&mut self, level: Level, seconds: f64, score: u32) {
+        match self
+            .records
+            .iter_mut()
+            .find(|record| record.level == level.id())
+        {
+            Some(record) => {
+                record.best_seconds = record.best_seconds.min(seconds);
+                record.best_score = record.best_score.max(score);
+            }
+            None => self.records.push(LevelRecord {
+                level: level.id(),
+                best_seconds: seconds,
+                best_score: score,
+            }),
+        }
+        if let Some(next) = level.next() {
+            self.unlock(next);
+        }
+    }
+}
+
+// helper function to read progress of any known version, bringing it up to the current one
+// There has only been one version so far.
+fn migrate_progress(text: &str) -> Result<Progress, String> {
+    let SaveVersion { version } = parse(text)?;
+    match version {
+        PROGRESS_VERSION => parse(text),
+        _ => Err(format!("unsupported progress version {}", version)),
+    }
+}
+
+// helper function to write a value to a RON file, creating its directory if needed
+pub fn write_ron<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let text = ron::ser::to_string_pretty(value, Default::default())
+        .map_err(|error| io::Error::new(ErrorKind::InvalidData, error.to_string()))?;
+
+    // Write to a temporary file first, so a crash halfway through never leaves a broken file
+    let temporary_path = path.with_extension("ron.tmp");
+    fs::write(&temporary_path, text)?;
+    fs::rename(&temporary_path, path)
+}
+
+// The save found when the game started, if there is one to continue
+pub struct SavedCampaign(pub Option<CampaignSave>);
+
+// File the campaign is saved to
+pub struct SaveFile(pub PathBuf);
+
+// File the progress is saved to
+pub struct ProgressFile(pub PathBuf);
+
+// Continues the saved campaign from the mission it was saved at
+pub struct ContinueCampaign;
+
+// helper function to find a file in the user's data directory, falling back to the working
+// directory if there is none
+pub fn user_data_path(file_name: &str) -> PathBuf {
+    match dirs::data_dir() {
+        Some(directory) => directory.join("tanks").join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+fn save_system(
+    current_level: Res<CurrentLevel>,
+    campaign: Res<Campaign>,
+    campaign_stats: Res<CampaignStats>,
+    seed: Res<SimulationSeed>,
+    save_file: Res<SaveFile>,
+    mut saved: ResMut<SavedCampaign>,
+) {
+    if let Some(level) = current_level.0 {
+        let save = CampaignSave {
+            version: SAVE_VERSION,
+            level: level.id(),
+            lives: campaign.lives,
+            missions_cleared: campaign.missions_cleared,
+            stats: campaign_stats.0.clone(),
+            seed: seed.0,
+        };
+        if let Err(error) = save.save(&save_file.0) {
+            warn!(
+                "Failed to save campaign {}: {}",
+                save_file.0.display(),
+                error
+            );
+        }
+        saved.0 = Some(save);
+    }
+}
+
+fn unlock_system(
+    current_level: Res<CurrentLevel>,
+    progress_file: Res<ProgressFile>,
+    mut progress: ResMut<Progress>,
+) {
+    // Reaching a level in any way unlocks it
+    if let Some(level) = current_level.0 {
+        if progress.unlock(level) {
+            save_progress(&progress, &progress_file);
+        }
+    }
+}
+
+fn level_record_system(
+    current_level: Res<CurrentLevel>,
+    mission_stats: Res<MissionStats>,
+    progress_file: Res<ProgressFile>,
+    mut progress: ResMut<Progress>,
+) {
+    if let Some(level) = current_level.0 {
+        progress.record_win(
+            level,
+            mission_stats.stats.seconds(),
+            mission_stats.stats.score,
+        );
+        save_progress(&progress, &progress_file);
+    }
+}
+
+// helper function to save the progress, reporting any error
+fn save_progress(progress: &Progress, progress_file: &ProgressFile) {
+    if let Err(error) = progress.save(&progress_file.0) {
+        warn!(
+            "Failed to save progress {}: {}",
+            progress_file.0.display(),
+            error
+        );
+    }
+}
+
+fn delete_save_system(save_file: Res<SaveFile>, mut saved: ResMut<SavedCampaign>) {
+    // A campaign that is over cannot be continued
+    saved.0 = None;
+    match fs::remove_file(&save_file.0) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            warn!("Failed to delete save {}: {}", save_file.0.display(), error)
+        }
+        _ => {}
+    }
+}
+
+fn continue_campaign_system(
+    mut events: EventReader<ContinueCampaign>,
+    saved: Res<SavedCampaign>,
+    mut campaign: ResMut<Campaign>,
+    mut campaign_stats: ResMut<CampaignStats>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut seed: ResMut<SimulationSeed>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    for _ in events.iter() {
+        if let Some(save) = &saved.0 {
+            seed.0 = save.seed;
+            campaign.lives = save.lives;
+            campaign.missions_cleared = save.missions_cleared;
+            campaign_stats.0 = save.stats.clone();
+            current_level.0 = Level::from_id(save.level);
+            game_state
+                .overwrite_set(GameState::Briefing)
+                .expect("Error: Failed to set Briefing state");
+        }
+    }
+}
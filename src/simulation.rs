@@ -0,0 +1,165 @@
+// The fixed rate simulation tick that all gameplay runs on, and the bookkeeping done at the start
+// of every tick.
+use crate::{
+    collision::{collider_index_system, wall_geometry_system, ColliderIndex, WallGeometry},
+    config::GameConfig,
+    flow::{playing_state_criteria, GameState},
+};
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+// Length of a single simulation tick in seconds
+pub const TICK_SECONDS: f64 = 1.0 / 60.0;
+
+// Upper limit on ticks run in one frame, so the game does not spiral trying to catch up after a
+// long hitch
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationClock>()
+            .init_resource::<GameConfig>()
+            .init_resource::<WallGeometry>()
+            .init_resource::<ColliderIndex>()
+            .add_stage_after(
+                CoreStage::Update,
+                SimulationStage,
+                Schedule::default()
+                    .with_run_criteria(simulation_tick_criteria.system())
+                    .with_stage(TickStage::Update, SystemStage::parallel())
+                    .with_stage_after(
+                        TickStage::Update,
+                        TickStage::Rules,
+                        SystemStage::parallel().with_run_criteria(playing_state_criteria),
+                    ),
+            )
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule.add_system_set_to_stage(
+                    TickStage::Update,
+                    SystemSet::on_update(GameState::Playing)
+                        .with_system(previous_position_system.label(SimulationSystem::Prepare))
+                        .with_system(wall_geometry_system.label(SimulationSystem::Prepare))
+                        .with_system(collider_index_system.label(SimulationSystem::Prepare)),
+                )
+            });
+    }
+}
+
+// Stage running the gameplay simulation at a fixed rate, after the regular update stage
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub struct SimulationStage;
+
+// Stages making up a single simulation tick. Commands are applied between stages, so the game
+// rules always see the entities spawned and despawned earlier in the same tick.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub enum TickStage {
+    Update,
+    Rules,
+}
+
+// Phases of a simulation tick, in the order they run
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum SimulationSystem {
+    // Bookkeeping such as rebuilding the spatial index
+    Prepare,
+    // Acting on the player's input
+    Input,
+    // Enemy tank decisions
+    Ai,
+    // Firing the weapons of tanks that asked to
+    Firing,
+    // Tank movement
+    Movement,
+    // Bullet movement and everything bullets run into
+    Collision,
+    // Cleaning up entities that left the arena
+    Resolution,
+    // Checking for mission success or failure
+    Rules,
+}
+
+// Seed for anything random in the simulation, saved with replays so runs can be reproduced
+pub struct SimulationSeed(pub u64);
+
+// Random numbers that play out the same every time they are given the same seed, such as the
+// moves of the evaluation bots
+pub struct SimulationRng(u64);
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> SimulationRng {
+        // Spread out similar seeds with splitmix64. Xorshift gets stuck on a state of zero.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        SimulationRng((z ^ (z >> 31)) | 1)
+    }
+
+    // xorshift64, returning a number between 0 and 1
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[derive(Default)]
+pub struct SimulationClock {
+    // Run exactly one tick per update instead of following real time
+    pub stepped: bool,
+    // Real time in seconds that has passed but has not been simulated yet
+    pub accumulator: f64,
+    // Whether the simulation stage is catching up on several ticks in the current frame
+    pub looping: bool,
+    // Number of ticks in the current frame so far
+    pub frame_ticks: u32,
+    // Number of ticks simulated since the game started
+    pub tick: u64,
+}
+
+// Position of an entity in the simulation, only changed on ticks
+#[derive(Component)]
+pub struct Position(pub Vec2);
+
+// Position of an entity before the last tick, used to interpolate rendering between ticks
+#[derive(Component)]
+pub struct PreviousPosition(pub Vec2);
+
+// Fixed timestep systems
+fn simulation_tick_criteria(time: Res<Time>, mut clock: ResMut<SimulationClock>) -> ShouldRun {
+    if clock.stepped {
+        clock.tick += 1;
+        return ShouldRun::Yes;
+    }
+
+    if !clock.looping {
+        clock.accumulator += time.delta_seconds_f64();
+        clock.frame_ticks = 0;
+    }
+
+    if clock.accumulator >= TICK_SECONDS {
+        clock.accumulator -= TICK_SECONDS;
+        clock.frame_ticks += 1;
+        clock.tick += 1;
+
+        // Drop any whole ticks still owed once the tick limit is reached
+        if clock.frame_ticks >= MAX_TICKS_PER_FRAME {
+            clock.accumulator %= TICK_SECONDS;
+            clock.looping = false;
+            return ShouldRun::Yes;
+        }
+
+        clock.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        clock.looping = false;
+        ShouldRun::No
+    }
+}
+
+fn previous_position_system(mut query: Query<(&Position, &mut PreviousPosition)>) {
+    for (position, mut previous_position) in query.iter_mut() {
+        previous_position.0 = position.0;
+    }
+}
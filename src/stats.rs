@@ -0,0 +1,238 @@
+// Statistics and score of the player in the current mission and across the campaign, collected
+// from the combat events.
+use crate::{
+    ai::EnemyKind,
+    combat::{BulletFired, BulletRicocheted, BulletsCancelled, TankDestroyed},
+    config::GameConfig,
+    flow::{GameState, MissionEnded, StartCampaign},
+    player::Player,
+    simulation::{SimulationStage, SimulationSystem, TickStage, TICK_SECONDS},
+};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MissionStats>()
+            .init_resource::<CampaignStats>()
+            .add_system(campaign_stats_reset_system)
+            .stage(SimulationStage, |schedule: &mut Schedule| {
+                schedule
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_enter(GameState::Briefing)
+                            .with_system(mission_stats_reset_system),
+                    )
+                    .add_system_set_to_stage(
+                        TickStage::Update,
+                        SystemSet::on_update(GameState::Playing).with_system(
+                            mission_stats_system
+                                .label(SimulationSystem::Resolution)
+                                .after(SimulationSystem::Collision),
+                        ),
+                    )
+                    .add_system_to_stage(
+                        TickStage::Rules,
+                        campaign_stats_system.after(SimulationSystem::Rules),
+                    )
+            });
+    }
+}
+
+// What the player achieved during a single mission, or a whole campaign
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    // Enemy tanks destroyed by the player, by type
+    pub kills: Vec<(EnemyKind, u32)>,
+    pub shots: u32,
+    // Player bullets that destroyed an enemy tank
+    pub hits: u32,
+    // Enemy tanks destroyed by player bullets that had ricocheted first
+    pub ricochet_kills: u32,
+    // Player bullets destroyed by running into another bullet
+    pub cancels: u32,
+    // Ticks spent in the mission
+    pub ticks: u64,
+    pub score: u32,
+}
+
+impl Stats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().map(|(_, count)| count).sum()
+    }
+
+    // Share of the shots fired that destroyed an enemy, if any shots were fired
+    pub fn accuracy(&self) -> Option<f32> {
+        if self.shots == 0 {
+            None
+        } else {
+            Some(self.hits as f32 / self.shots as f32)
+        }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 * TICK_SECONDS
+    }
+
+    fn add_kill(&mut self, kind: EnemyKind, count: u32) {
+        match self.kills.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, kills)) => *kills += count,
+            None => self.kills.push((kind, count)),
+        }
+    }
+
+    fn add(&mut self, other: &Stats) {
+        for (kind, count) in other.kills.iter() {
+            self.add_kill(*kind, *count);
+        }
+        self.shots += other.shots;
+        self.hits += other.hits;
+        self.ricochet_kills += other.ricochet_kills;
+        self.cancels += other.cancels;
+        self.ticks += other.ticks;
+        self.score += other.score;
+    }
+}
+
+#[derive(Default)]
+pub struct MissionStats {
+    pub stats: Stats,
+    // Multiplier the score was given for winning the mission quickly, once it is won
+    pub time_multiplier: Option<f32>,
+    // Kills in the current combo so far, and the tick of the last one
+    combo: u32,
+    last_kill_tick: u64,
+    // Bullets fired by the player during the mission
+    player_bullets: HashSet<Entity>,
+    // Player bullets that have ricocheted at least once
+    ricocheted: HashSet<Entity>,
+}
+
+// Stats of every mission played in the campaign so far, won or lost
+#[derive(Default)]
+pub struct CampaignStats(pub Stats);
+
+fn mission_stats_reset_system(mut mission_stats: ResMut<MissionStats>) {
+    *mission_stats = MissionStats::default();
+}
+
+fn campaign_stats_reset_system(
+    mut events: EventReader<StartCampaign>,
+    mut campaign_stats: ResMut<CampaignStats>,
+) {
+    if events.iter().count() > 0 {
+        *campaign_stats = CampaignStats::default();
+    }
+}
+
+#[derive(SystemParam)]
+struct CombatEvents<'w, 's> {
+    bullet_fired: EventReader<'w, 's, BulletFired>,
+    bullet_ricocheted: EventReader<'w, 's, BulletRicocheted>,
+    bullets_cancelled: EventReader<'w, 's, BulletsCancelled>,
+    tank_destroyed: EventReader<'w, 's, TankDestroyed>,
+}
+
+fn mission_stats_system(
+    mut mission_stats: ResMut<MissionStats>,
+    config: Res<GameConfig>,
+    player_query: Query<Entity, With<Player>>,
+    enemy_query: Query<&EnemyKind>,
+    mut events: CombatEvents,
+) {
+    let MissionStats {
+        stats,
+        combo,
+        last_kill_tick,
+        player_bullets,
+        ricocheted,
+        ..
+    } = &mut *mission_stats;
+    stats.ticks += 1;
+
+    // Tell player bullets apart by who fired them, since the player may be gone by the time they
+    // hit something
+    for event in events.bullet_fired.iter() {
+        if player_query.get(event.owner).is_ok() {
+            stats.shots += 1;
+            player_bullets.insert(event.bullet);
+        }
+    }
+
+    for event in events.bullet_ricocheted.iter() {
+        if player_bullets.contains(&event.bullet) {
+            ricocheted.insert(event.bullet);
+        }
+    }
+
+    for event in events.bullets_cancelled.iter() {
+        for bullet in [event.first, event.second] {
+            if player_bullets.contains(&bullet) {
+                stats.cancels += 1;
+                stats.score += config.score.cancel_bonus;
+            }
+        }
+    }
+
+    // Destroyed tanks are only despawned at the end of the tick, so their type can still be found
+    for event in events.tank_destroyed.iter() {
+        if !player_bullets.contains(&event.bullet) {
+            continue;
+        }
+        if let Ok(kind) = enemy_query.get(event.victim) {
+            stats.add_kill(*kind, 1);
+            stats.hits += 1;
+            stats.score += config.enemy(*kind).points;
+            if ricocheted.contains(&event.bullet) {
+                stats.ricochet_kills += 1;
+                stats.score += config.score.ricochet_kill_bonus;
+            }
+
+            // Every kill soon after the last one is worth more than the one before
+            let combo_ticks = (config.score.combo_seconds as f64 / TICK_SECONDS) as u64;
+            if *combo > 0 && stats.ticks - *last_kill_tick <= combo_ticks {
+                *combo += 1;
+            } else {
+                *combo = 1;
+            }
+            stats.score += config.score.combo_bonus * (*combo - 1);
+            *last_kill_tick = stats.ticks;
+        }
+    }
+}
+
+fn campaign_stats_system(
+    mut events: EventReader<MissionEnded>,
+    config: Res<GameConfig>,
+    mut mission_stats: ResMut<MissionStats>,
+    mut campaign_stats: ResMut<CampaignStats>,
+) {
+    // Lost missions are played again against the same enemies, so only won missions add to the
+    // campaign score. Everything else done in a lost mission still counts.
+    for event in events.iter() {
+        if event.won {
+            let multiplier = time_multiplier(&config, mission_stats.stats.seconds());
+            mission_stats.stats.score =
+                (mission_stats.stats.score as f32 * multiplier).round() as u32;
+            mission_stats.time_multiplier = Some(multiplier);
+            campaign_stats.0.add(&mission_stats.stats);
+        } else {
+            campaign_stats.0.add(&Stats {
+                score: 0,
+                ..mission_stats.stats.clone()
+            });
+        }
+    }
+}
+
+// helper function to find the multiplier for winning a mission in the given time
+fn time_multiplier(config: &GameConfig, seconds: f64) -> f32 {
+    if config.score.par_seconds <= 0.0 {
+        return 1.0;
+    }
+    let remaining = (1.0 - seconds as f32 / config.score.par_seconds).max(0.0);
+    1.0 + (config.score.max_time_multiplier - 1.0).max(0.0) * remaining
+}
@@ -0,0 +1,1297 @@
+// Gameplay scenarios run against the headless simulation, one tick per update
+use crate::{
+    ai::{BrownTank, Enemy, EnemyKind},
+    collision::{Collider, Hitbox, WallGeometry},
+    combat::{
+        Bullet, BulletFired, BulletOwner, BulletRicocheted, BulletsCancelled, FireIntent,
+        Projectile, RicochetCount, TankDestroyed, Weapon,
+    },
+    config::{GameConfig, CONFIG_PATH},
+    env::{Action, Env},
+    flow::{Campaign, GameState, MissionEnded},
+    highscore::{today, HighScore, HighScores, MAX_HIGH_SCORES},
+    level::{Creator, CurrentLevel, Level},
+    options::Options,
+    player::{CursorPosition, Player, PlayerInput},
+    replay::{
+        Replay, ReplayDirectory, ReplayPlayback, ReplayPlaybackPlugin, ReplayRecorder,
+        ReplayRecordingPlugin, TickInput,
+    },
+    save::{CampaignSave, Progress, SAVE_VERSION},
+    simulation::{
+        Position, PreviousPosition, SimulationClock, SimulationSeed, SimulationStage,
+        SimulationSystem, TickStage,
+    },
+    stats::{CampaignStats, MissionStats, Stats},
+    ui::{hud_system, Hud},
+    SimulationPlugins,
+};
+use bevy::{app::Events, ecs::system::CommandQueue, prelude::*};
+use std::marker::PhantomData;
+
+// Number of ticks the 4 second win and lose timers take to run out
+const TIMER_TICKS: u32 = 241;
+// Ticks from the one setting up a mission until its briefing is over
+const BRIEFING_TICKS: u32 = 180;
+
+struct Scenario {
+    app: App,
+}
+
+impl Scenario {
+    // An empty arena with the mission already in progress, to be filled by the test
+    fn new() -> Scenario {
+        let mut app = App::new();
+        app.insert_resource(State::new(GameState::Playing));
+        Scenario::with_app(app, None)
+    }
+
+    // The given level, set up and with its briefing over
+    fn with_level(level: Option<Level>) -> Scenario {
+        let mut scenario = Scenario::with_app(App::new(), level);
+        scenario.finish_briefing();
+        scenario
+    }
+
+    fn with_app(mut app: App, level: Option<Level>) -> Scenario {
+        app.insert_resource(SimulationClock {
+            stepped: true,
+            ..Default::default()
+        })
+        .add_plugins(MinimalPlugins)
+        .add_plugins(SimulationPlugins)
+        .insert_resource(CurrentLevel(level))
+        .insert_resource(SimulationSeed(0));
+
+        Scenario { app }
+    }
+
+    // Steps through the briefing at the start of a mission, returning the number of ticks it took
+    fn finish_briefing(&mut self) -> u32 {
+        let mut ticks = 0;
+        while ticks == 0 || self.state() == GameState::Briefing {
+            self.step(1);
+            ticks += 1;
+        }
+        ticks
+    }
+
+    fn spawn(&mut self, create: impl FnOnce(&mut Creator) -> Entity) -> Entity {
+        let mut queue = CommandQueue::default();
+        let entity = create(&mut Creator {
+            commands: Commands::new(&mut queue, &self.app.world),
+            config: self.app.world.get_resource().unwrap(),
+        });
+        queue.apply(&mut self.app.world);
+        entity
+    }
+
+    // A bullet that ricochets as many times as the player's bullets do
+    fn spawn_bullet(&mut self, position: Vec2, velocity: Vec2, owner: Entity) -> Entity {
+        self.spawn(|creator| {
+            let ricochets = creator.config.player.weapon.ricochets;
+            creator.create_projectile(Projectile::Bullet, position, velocity, ricochets, owner)
+        })
+    }
+
+    // An entity that stands in for the tank that fired a bullet
+    fn owner(&mut self) -> Entity {
+        self.app.world.spawn().id()
+    }
+
+    // A player that cannot move, shoot or be hit, so the mission is never lost
+    fn idle_player(&mut self) {
+        self.app.world.spawn().insert(Player { speed: 0.0 });
+    }
+
+    // An enemy that cannot shoot or be hit, so the mission is never won
+    fn idle_enemy(&mut self) {
+        self.app.world.spawn().insert(Enemy);
+    }
+
+    // An enemy that can be hit but never shoots back
+    fn target_enemy(&mut self, x: f32, y: f32) -> Entity {
+        self.app
+            .world
+            .spawn()
+            .insert(Enemy)
+            .insert(EnemyKind::Brown)
+            .insert(Position(Vec2::new(x, y)))
+            .insert(PreviousPosition(Vec2::new(x, y)))
+            .insert(Hitbox(Vec2::new(32.0, 32.0)))
+            .insert(Collider::Enemy)
+            .id()
+    }
+
+    fn fire_at(&mut self, target: Vec2) {
+        self.app
+            .world
+            .get_resource_mut::<CursorPosition>()
+            .unwrap()
+            .pos = target;
+        self.app
+            .world
+            .get_resource_mut::<PlayerInput>()
+            .unwrap()
+            .fire = true;
+    }
+
+    // Keeps every event of the given type sent from now on, to be checked with `recorded`
+    fn record<E: Clone + Send + Sync + 'static>(&mut self) {
+        self.app
+            .insert_resource(Recorded::<E>(vec![]))
+            .add_system_to_stage(CoreStage::Last, record_system::<E>);
+    }
+
+    fn recorded<E: Clone + Send + Sync + 'static>(&self) -> Vec<E> {
+        self.app
+            .world
+            .get_resource::<Recorded<E>>()
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    // Counts the ticks at the end of which the given resource had changed, to be checked with
+    // `changes`
+    fn count_changes<R: Send + Sync + 'static>(&mut self) {
+        self.app
+            .insert_resource(Changes::<R>(0, PhantomData))
+            .add_system_to_stage(CoreStage::Last, change_count_system::<R>);
+    }
+
+    fn changes<R: Send + Sync + 'static>(&self) -> u32 {
+        self.app.world.get_resource::<Changes<R>>().unwrap().0
+    }
+
+    fn fire_intent(&mut self, tank: Entity, direction: Vec2) {
+        self.app
+            .world
+            .get_resource_mut::<Events<FireIntent>>()
+            .unwrap()
+            .send(FireIntent { tank, direction });
+    }
+
+    fn set_state(
+        &mut self,
+        change: impl FnOnce(&mut State<GameState>) -> Result<(), bevy::ecs::schedule::StateError>,
+    ) {
+        change(&mut self.app.world.get_resource_mut().unwrap()).unwrap();
+    }
+
+    fn step(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    fn exists(&self, entity: Entity) -> bool {
+        self.app.world.get_entity(entity).is_some()
+    }
+
+    fn position(&self, entity: Entity) -> Vec2 {
+        self.app.world.get::<Position>(entity).unwrap().0
+    }
+
+    fn velocity(&self, entity: Entity) -> Vec2 {
+        self.app.world.get::<Bullet>(entity).unwrap().velocity
+    }
+
+    fn ricochets(&self, entity: Entity) -> u32 {
+        self.app.world.get::<RicochetCount>(entity).unwrap().0
+    }
+
+    fn state(&self) -> GameState {
+        self.app
+            .world
+            .get_resource::<State<GameState>>()
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    fn count<T: Component>(&mut self) -> usize {
+        self.entities::<T>().len()
+    }
+
+    fn entities<T: Component>(&mut self) -> Vec<Entity> {
+        self.app
+            .world
+            .query_filtered::<Entity, With<T>>()
+            .iter(&self.app.world)
+            .collect()
+    }
+
+    fn bullets(&mut self) -> Vec<Entity> {
+        self.entities::<Bullet>()
+    }
+
+    fn owned_bullets(&mut self, owner: Entity) -> usize {
+        self.bullets()
+            .into_iter()
+            .filter(|bullet| self.app.world.get::<BulletOwner>(*bullet).unwrap().0 == owner)
+            .count()
+    }
+}
+
+struct Recorded<E>(Vec<E>);
+
+fn record_system<E: Clone + Send + Sync + 'static>(
+    mut events: EventReader<E>,
+    mut recorded: ResMut<Recorded<E>>,
+) {
+    recorded.0.extend(events.iter().cloned());
+}
+
+struct Changes<R>(u32, PhantomData<R>);
+
+fn change_count_system<R: Send + Sync + 'static>(
+    resource: Res<R>,
+    mut changes: ResMut<Changes<R>>,
+) {
+    if resource.is_changed() {
+        changes.0 += 1;
+    }
+}
+
+// Positions of every entity after each simulation tick. Entity ids are left out, since the
+// systems setting up a mission may spawn their entities in any order.
+#[derive(Default)]
+struct History(Vec<(u64, Vec<Vec2>)>);
+
+fn history_system(
+    clock: Res<SimulationClock>,
+    query: Query<&Position>,
+    mut history: ResMut<History>,
+) {
+    let positions = query.iter().map(|position| position.0).collect();
+    history.0.push((clock.tick, positions));
+}
+
+// helper function to play the first level in real time with the given frame times, repeated until
+// the given number of ticks has been simulated, returning the positions after every tick
+fn play_with_frame_times(frame_seconds: &[f64], ticks: u64) -> Vec<(u64, Vec<Vec2>)> {
+    // Without the core plugins time never passes on its own, so frames only last as long as the
+    // time added to the simulation clock by hand
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .init_resource::<History>()
+        .add_plugins(SimulationPlugins)
+        .insert_resource(CurrentLevel(Some(Level::L1)))
+        .insert_resource(SimulationSeed(0))
+        .insert_resource(PlayerInput {
+            direction: Vec2::new(-1.0, 0.0),
+            fire: true,
+        })
+        .stage(SimulationStage, |schedule: &mut Schedule| {
+            schedule.add_system_to_stage(
+                TickStage::Update,
+                history_system.after(SimulationSystem::Resolution),
+            )
+        });
+
+    for seconds in frame_seconds.iter().cycle() {
+        let mut clock = app.world.get_resource_mut::<SimulationClock>().unwrap();
+        if clock.tick >= ticks {
+            break;
+        }
+        clock.accumulator += seconds;
+        app.update();
+    }
+
+    let mut history = app.world.remove_resource::<History>().unwrap().0;
+    history.truncate(ticks as usize);
+    history
+}
+
+#[test]
+fn simulation_does_not_depend_on_frame_times() {
+    // A steady 60 frames per second, against uneven frames and frames long enough to hit the
+    // limit on ticks per frame
+    let ticks = 400;
+    let steady = play_with_frame_times(&[1.0 / 60.0], ticks);
+    assert_eq!(steady.len(), ticks as usize);
+    assert!(steady.iter().map(|(tick, _)| *tick).eq(1..=ticks));
+    assert_eq!(
+        play_with_frame_times(&[0.004, 0.03, 0.021, 0.05], ticks),
+        steady
+    );
+    assert_eq!(play_with_frame_times(&[0.2, 0.001], ticks), steady);
+}
+
+#[test]
+fn bullet_fired_at_45_degrees_ricochets_off_wall() {
+    let mut scenario = Scenario::new();
+    scenario.idle_enemy();
+    scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.spawn(|creator| creator.create_wall(32.0, 64.0));
+
+    scenario.fire_at(Vec2::new(100.0, 100.0));
+    scenario.step(1);
+    let bullets = scenario.bullets();
+    assert_eq!(bullets.len(), 1);
+    let bullet = bullets[0];
+
+    scenario.step(40);
+    assert!(scenario.exists(bullet));
+    assert_eq!(scenario.ricochets(bullet), 1);
+    let velocity = scenario.velocity(bullet);
+    assert!(velocity.x > 0.0 && velocity.y < 0.0);
+    assert!(scenario.position(bullet).y < 44.0);
+}
+
+#[test]
+fn bullet_is_destroyed_at_ricochet_limit() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    scenario.spawn(|creator| creator.create_wall(0.0, 64.0));
+    scenario.spawn(|creator| creator.create_wall(0.0, -64.0));
+    let bullet = scenario.spawn_bullet(Vec2::ZERO, Vec2::new(0.0, 150.0), owner);
+
+    scenario.step(35);
+    assert!(scenario.exists(bullet));
+    assert_eq!(scenario.ricochets(bullet), 1);
+
+    scenario.step(35);
+    assert!(!scenario.exists(bullet));
+    assert_eq!(scenario.count::<Collider>(), 2);
+}
+
+#[test]
+fn fresh_bullet_does_not_destroy_its_owner() {
+    let mut scenario = Scenario::new();
+    scenario.idle_enemy();
+    let player = scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+
+    scenario.fire_at(Vec2::new(100.0, 100.0));
+    scenario.step(5);
+    assert!(scenario.exists(player));
+    assert_eq!(scenario.bullets().len(), 1);
+    assert_eq!(scenario.state(), GameState::Playing);
+}
+
+#[test]
+fn ricocheted_bullet_destroys_its_owner() {
+    let mut scenario = Scenario::new();
+    scenario.idle_enemy();
+    let player = scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.spawn(|creator| creator.create_wall(0.0, 64.0));
+
+    scenario.fire_at(Vec2::new(0.0, 100.0));
+    scenario.step(40);
+    assert!(!scenario.exists(player));
+    assert!(scenario.bullets().is_empty());
+    assert_eq!(scenario.state(), GameState::Lose);
+}
+
+#[test]
+fn ricocheted_bullet_kill_sends_events() {
+    let mut scenario = Scenario::new();
+    scenario.record::<BulletFired>();
+    scenario.record::<BulletRicocheted>();
+    scenario.record::<TankDestroyed>();
+    scenario.record::<MissionEnded>();
+    scenario.idle_enemy();
+    let player = scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.spawn(|creator| creator.create_wall(0.0, 64.0));
+
+    scenario.fire_at(Vec2::new(0.0, 100.0));
+    scenario.step(40);
+
+    let fired = scenario.recorded::<BulletFired>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].owner, player);
+
+    let ricocheted = scenario.recorded::<BulletRicocheted>();
+    assert_eq!(ricocheted.len(), 1);
+    assert_eq!(ricocheted[0].bullet, fired[0].bullet);
+    assert_eq!(ricocheted[0].normal, Vec2::new(0.0, -1.0));
+    assert_eq!(ricocheted[0].ricochets, 1);
+
+    let destroyed = scenario.recorded::<TankDestroyed>();
+    assert_eq!(destroyed.len(), 1);
+    assert_eq!(destroyed[0].victim, player);
+    assert_eq!(destroyed[0].killer, player);
+    assert_eq!(destroyed[0].bullet, fired[0].bullet);
+
+    let ended = scenario.recorded::<MissionEnded>();
+    assert_eq!(ended.len(), 1);
+    assert!(!ended[0].won);
+}
+
+#[test]
+fn weapon_respects_cooldown_and_capacity() {
+    let mut scenario = Scenario::new();
+    scenario.idle_enemy();
+    let tank = scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    let mut weapon = scenario.app.world.get_mut::<Weapon>(tank).unwrap();
+    weapon.cooldown = 0.5;
+    weapon.capacity = 2;
+
+    // Only one of several intents in the same tick goes off
+    scenario.fire_intent(tank, Vec2::new(1.0, 0.0));
+    scenario.fire_intent(tank, Vec2::new(0.0, 1.0));
+    scenario.step(1);
+    assert_eq!(scenario.bullets().len(), 1);
+
+    scenario.fire_intent(tank, Vec2::new(1.0, 0.0));
+    scenario.step(1);
+    assert_eq!(scenario.bullets().len(), 1);
+
+    scenario.step(30);
+    scenario.fire_intent(tank, Vec2::new(-1.0, 0.0));
+    scenario.step(1);
+    assert_eq!(scenario.bullets().len(), 2);
+
+    // Both bullets are still in the air once the weapon has cooled down again
+    scenario.step(30);
+    scenario.fire_intent(tank, Vec2::new(0.0, -1.0));
+    scenario.step(1);
+    assert_eq!(scenario.bullets().len(), 2);
+}
+
+#[test]
+fn bullet_destroys_enemy_and_wins_mission() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    let owner = scenario.owner();
+    let tank = scenario.spawn(|creator| creator.create_brown_tank(100.0, 0.0));
+    let bullet = scenario.spawn_bullet(Vec2::ZERO, Vec2::new(150.0, 0.0), owner);
+
+    scenario.step(45);
+    assert!(!scenario.exists(tank));
+    assert!(!scenario.exists(bullet));
+    assert_eq!(scenario.state(), GameState::Win);
+}
+
+#[test]
+fn mission_stats_count_player_shots_and_kills() {
+    let mut scenario = Scenario::new();
+    scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.target_enemy(100.0, 0.0);
+    scenario.fire_at(Vec2::new(100.0, 0.0));
+
+    scenario.step(45);
+    assert_eq!(scenario.state(), GameState::Win);
+    let stats = &scenario
+        .app
+        .world
+        .get_resource::<MissionStats>()
+        .unwrap()
+        .stats;
+    assert_eq!(stats.kills, vec![(EnemyKind::Brown, 1)]);
+    assert_eq!(stats.shots, 1);
+    assert_eq!(stats.accuracy(), Some(1.0));
+    assert_eq!(stats.ricochet_kills, 0);
+    assert_eq!(stats.cancels, 0);
+    assert!(stats.ticks > 0);
+    assert_eq!(
+        &scenario
+            .app
+            .world
+            .get_resource::<CampaignStats>()
+            .unwrap()
+            .0,
+        stats
+    );
+}
+
+#[test]
+fn lost_missions_add_stats_but_no_score_to_the_campaign() {
+    let mut scenario = Scenario::new();
+    let player = scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.target_enemy(100.0, 0.0);
+    scenario.idle_enemy();
+    scenario.fire_at(Vec2::new(100.0, 0.0));
+    scenario.step(45);
+
+    let owner = scenario.owner();
+    scenario.spawn_bullet(Vec2::new(-100.0, 0.0), Vec2::new(150.0, 0.0), owner);
+    scenario.step(45);
+    assert!(!scenario.exists(player));
+    assert_eq!(scenario.state(), GameState::Lose);
+
+    let world = &scenario.app.world;
+    assert_eq!(
+        world.get_resource::<MissionStats>().unwrap().stats.score,
+        100
+    );
+    let campaign_stats = &world.get_resource::<CampaignStats>().unwrap().0;
+    assert_eq!(campaign_stats.kills, vec![(EnemyKind::Brown, 1)]);
+    assert_eq!(campaign_stats.shots, 1);
+    assert_eq!(campaign_stats.score, 0);
+}
+
+#[test]
+fn quick_kills_score_combo_and_time_bonus() {
+    let mut scenario = Scenario::new();
+    scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    scenario.target_enemy(100.0, 0.0);
+    scenario.target_enemy(-100.0, 0.0);
+    scenario.fire_at(Vec2::new(100.0, 0.0));
+    // Let the first bullet clear the player, or the second would run into it
+    scenario.step(10);
+    scenario.fire_at(Vec2::new(-100.0, 0.0));
+
+    scenario.step(45);
+    assert_eq!(scenario.state(), GameState::Win);
+    let mission_stats = scenario.app.world.get_resource::<MissionStats>().unwrap();
+    let multiplier = mission_stats.time_multiplier.unwrap();
+    assert!(multiplier > 1.9 && multiplier < 2.0);
+    // Two kills worth 100 points each, and a combo bonus for the second
+    assert_eq!(
+        mission_stats.stats.score,
+        (250.0 * multiplier).round() as u32
+    );
+}
+
+// Last values the HUD was given to show
+struct LastHud(Hud);
+
+fn last_hud_system(In(hud): In<Hud>, mut last_hud: ResMut<LastHud>) {
+    last_hud.0 = hud;
+}
+
+#[test]
+fn hud_shows_the_game_state_after_a_kill() {
+    let mut scenario = Scenario::new();
+    scenario
+        .app
+        .insert_resource(CurrentLevel(Some(Level::L2)))
+        .insert_resource(LastHud(Hud::default()))
+        .add_system_to_stage(CoreStage::Last, hud_system.chain(last_hud_system));
+    scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+    let target = scenario.target_enemy(100.0, 0.0);
+    // Keep the mission going after the kill
+    scenario.target_enemy(-300.0, 200.0);
+    scenario.fire_at(Vec2::new(100.0, 0.0));
+
+    scenario.step(1);
+    let hud = scenario
+        .app
+        .world
+        .get_resource::<LastHud>()
+        .unwrap()
+        .0
+        .clone();
+    assert_eq!(hud.bullets_available, 4);
+    assert_eq!(hud.bullets_in_flight, 1);
+    assert_eq!(hud.enemies, 2);
+
+    scenario.step(45);
+    assert!(!scenario.exists(target));
+    assert_eq!(scenario.state(), GameState::Playing);
+    let world = &scenario.app.world;
+    let score = world.get_resource::<CampaignStats>().unwrap().0.score
+        + world.get_resource::<MissionStats>().unwrap().stats.score;
+    assert_eq!(score, 100);
+    assert_eq!(
+        world.get_resource::<LastHud>().unwrap().0,
+        Hud {
+            mission: 2,
+            enemies: 1,
+            lives: world.get_resource::<Campaign>().unwrap().lives,
+            score,
+            bullets_available: 5,
+            bullets_in_flight: 0,
+        }
+    );
+}
+
+#[test]
+fn bullets_destroy_each_other() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    let left = scenario.spawn_bullet(Vec2::new(-100.0, 0.0), Vec2::new(150.0, 0.0), owner);
+    let right = scenario.spawn_bullet(Vec2::new(100.0, 0.0), Vec2::new(-150.0, 0.0), owner);
+
+    scenario.record::<BulletsCancelled>();
+
+    scenario.step(50);
+    assert!(!scenario.exists(left));
+    assert!(!scenario.exists(right));
+    let cancelled = scenario.recorded::<BulletsCancelled>();
+    assert_eq!(cancelled.len(), 1);
+    assert!([left, right].contains(&cancelled[0].first));
+    assert!([left, right].contains(&cancelled[0].second));
+}
+
+#[test]
+fn fast_bullet_does_not_pass_through_wall() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    scenario.spawn(|creator| creator.create_wall(0.0, 64.0));
+    // Moves 100 pixels per tick, much further than the wall is thick
+    let bullet = scenario.spawn_bullet(Vec2::ZERO, Vec2::new(0.0, 6000.0), owner);
+
+    scenario.step(1);
+    assert_eq!(scenario.ricochets(bullet), 1);
+    assert!(scenario.velocity(bullet).y < 0.0);
+    assert!(scenario.position(bullet).y < 44.0);
+}
+
+#[test]
+fn bullet_reflects_once_off_seam_between_walls() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    scenario.spawn(|creator| creator.create_wall(-16.0, 64.0));
+    scenario.spawn(|creator| creator.create_wall(16.0, 64.0));
+    // Hits the bottom of the walls right where the two tiles meet
+    let bullet = scenario.spawn_bullet(Vec2::new(-44.0, 0.0), Vec2::new(100.0, 100.0), owner);
+
+    scenario.step(40);
+    assert!(scenario.exists(bullet));
+    assert_eq!(scenario.ricochets(bullet), 1);
+    assert_eq!(scenario.velocity(bullet), Vec2::new(100.0, -100.0));
+}
+
+#[test]
+fn bullet_hitting_corner_bounces_straight_back() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    scenario.spawn(|creator| creator.create_wall(0.0, 64.0));
+    // Reaches the bottom left corner of the wall along the diagonal
+    let bullet = scenario.spawn_bullet(Vec2::new(-64.0, 0.0), Vec2::new(100.0, 100.0), owner);
+
+    scenario.step(40);
+    assert!(scenario.exists(bullet));
+    assert_eq!(scenario.ricochets(bullet), 1);
+    let velocity = scenario.velocity(bullet);
+    assert!((velocity - Vec2::new(-100.0, -100.0)).length() < 1e-3);
+}
+
+#[test]
+fn bullet_leaving_arena_is_removed() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    let bullet = scenario.spawn_bullet(Vec2::new(630.0, 0.0), Vec2::new(150.0, 0.0), owner);
+
+    scenario.step(10);
+    assert!(!scenario.exists(bullet));
+}
+
+#[test]
+fn mission_is_won_without_enemies() {
+    let mut scenario = Scenario::new();
+    scenario.spawn(|creator| creator.create_player(0.0, 0.0));
+
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Win);
+}
+
+#[test]
+fn mission_is_won_when_player_and_last_enemy_die_together() {
+    let mut scenario = Scenario::new();
+    let owner = scenario.owner();
+    let player = scenario.spawn(|creator| creator.create_player(-100.0, 0.0));
+    let enemy = scenario.target_enemy(100.0, 0.0);
+    let velocity = Vec2::new(0.0, -150.0);
+    scenario.spawn_bullet(Vec2::new(-100.0, 100.0), velocity, owner);
+    scenario.spawn_bullet(Vec2::new(100.0, 100.0), velocity, owner);
+
+    scenario.step(60);
+    assert!(!scenario.exists(player));
+    assert!(!scenario.exists(enemy));
+    assert_eq!(scenario.state(), GameState::Win);
+}
+
+#[test]
+fn lost_mission_restarts_after_timer() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario.step(1);
+    let player = scenario.entities::<Player>()[0];
+    scenario.app.world.despawn(player);
+
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Lose);
+    assert_eq!(
+        scenario.app.world.get_resource::<Campaign>().unwrap().lives,
+        2
+    );
+
+    scenario.step(TIMER_TICKS);
+    assert_eq!(scenario.state(), GameState::Briefing);
+    assert_eq!(scenario.count::<Player>(), 1);
+    assert_eq!(scenario.count::<BrownTank>(), 1);
+}
+
+#[test]
+fn briefing_freezes_tanks_until_mission_starts() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario.set_state(|state| state.set(GameState::Restarting));
+    scenario.step(1);
+    assert_eq!(scenario.state(), GameState::Briefing);
+
+    // The enemy would fire on the first tick it gets to act
+    let enemy = scenario.entities::<BrownTank>()[0];
+    scenario.step(60);
+    assert_eq!(scenario.state(), GameState::Briefing);
+    assert_eq!(scenario.owned_bullets(enemy), 0);
+
+    assert_eq!(scenario.finish_briefing(), BRIEFING_TICKS - 61);
+    assert_eq!(scenario.state(), GameState::Playing);
+    scenario.step(1);
+    assert_eq!(scenario.owned_bullets(enemy), 1);
+}
+
+#[test]
+fn losing_last_life_ends_campaign() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario
+        .app
+        .world
+        .get_resource_mut::<Campaign>()
+        .unwrap()
+        .lives = 1;
+    scenario.step(1);
+    let player = scenario.entities::<Player>()[0];
+    scenario.app.world.despawn(player);
+
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Lose);
+
+    scenario.step(TIMER_TICKS);
+    assert_eq!(scenario.state(), GameState::GameOver);
+    assert_eq!(scenario.count::<Player>(), 0);
+    assert_eq!(scenario.count::<BrownTank>(), 0);
+
+    // The game over screen lasts a little longer than the other timers
+    scenario.step(301);
+    assert_eq!(scenario.state(), GameState::MainMenu);
+}
+
+#[test]
+fn won_mission_advances_to_next_level_after_timer() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario.step(1);
+    let enemies = scenario.entities::<Enemy>();
+    for enemy in enemies {
+        scenario.app.world.despawn(enemy);
+    }
+
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Win);
+
+    scenario.step(TIMER_TICKS);
+    assert_eq!(scenario.state(), GameState::Briefing);
+    assert_eq!(
+        scenario.app.world.get_resource::<CurrentLevel>().unwrap().0,
+        Some(Level::L2)
+    );
+    assert_eq!(scenario.count::<BrownTank>(), 2);
+}
+
+#[test]
+fn shipped_config_matches_defaults() {
+    let text = std::fs::read_to_string(CONFIG_PATH).unwrap();
+    let config: GameConfig = ron::from_str(&text).unwrap();
+    assert_eq!(config, GameConfig::default());
+}
+
+#[test]
+fn high_scores_are_validated_on_load() {
+    let path = std::env::temp_dir().join(format!("tanks_validated_{}.ron", std::process::id()));
+    std::fs::write(&path, "not a high score table").unwrap();
+    assert!(HighScores::load(&path).is_err());
+
+    // Entries with a bad name or date are dropped, and the rest sorted
+    std::fs::write(
+        &path,
+        r#"(entries: [
+            (name: "AAA", score: 100, missions: 1, date: "2024-01-02"),
+            (name: "", score: 900, missions: 2, date: "2024-01-02"),
+            (name: "BBB", score: 300, missions: 2, date: "yesterday"),
+            (name: "CCC", score: 200, missions: 2, date: "2024-01-03"),
+        ])"#,
+    )
+    .unwrap();
+    let high_scores = HighScores::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let names: Vec<_> = high_scores
+        .entries
+        .iter()
+        .map(|entry| &entry.name)
+        .collect();
+    assert_eq!(names, ["CCC", "AAA"]);
+}
+
+#[test]
+fn high_score_table_keeps_best_scores() {
+    let high_score = |name: &str, score| HighScore {
+        name: name.to_string(),
+        score,
+        missions: 1,
+        date: today(),
+    };
+    let mut high_scores = HighScores::default();
+    for score in 1..=MAX_HIGH_SCORES as u32 {
+        assert!(high_scores.insert(high_score("AAA", score * 100)).is_some());
+    }
+    assert!(!high_scores.qualifies(100));
+    assert_eq!(high_scores.insert(high_score("BBB", 50)), None);
+    // Ties rank below the score that was set first
+    assert_eq!(
+        high_scores.insert(high_score("CCC", 200)),
+        Some(MAX_HIGH_SCORES - 1)
+    );
+    assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+
+    let path = std::env::temp_dir().join(format!("tanks_saved_{}.ron", std::process::id()));
+    high_scores.save(&path).unwrap();
+    let loaded = HighScores::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, high_scores);
+}
+
+#[test]
+fn campaign_save_round_trips_and_rejects_unknown_versions() {
+    let path = std::env::temp_dir().join(format!("tanks_campaign_{}.ron", std::process::id()));
+    let save = CampaignSave {
+        version: SAVE_VERSION,
+        level: Level::L2.id(),
+        lives: 2,
+        missions_cleared: 1,
+        stats: Stats {
+            kills: vec![(EnemyKind::Brown, 1)],
+            shots: 3,
+            hits: 1,
+            score: 150,
+            ..Default::default()
+        },
+        seed: 42,
+    };
+    save.save(&path).unwrap();
+    assert_eq!(CampaignSave::load(&path).unwrap(), save);
+
+    let newer = CampaignSave {
+        version: SAVE_VERSION + 1,
+        ..save.clone()
+    };
+    newer.save(&path).unwrap();
+    assert!(CampaignSave::load(&path).is_err());
+
+    let unknown_level = CampaignSave { level: 0, ..save };
+    unknown_level.save(&path).unwrap();
+    assert!(CampaignSave::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn version_1_campaign_save_is_migrated() {
+    let path = std::env::temp_dir().join(format!("tanks_campaign_v1_{}.ron", std::process::id()));
+    std::fs::write(
+        &path,
+        "(version: 1, level: 2, lives: 1, missions_cleared: 3, \
+         stats: (kills: [(Brown, 4)], shots: 9, score: 700))",
+    )
+    .unwrap();
+
+    let save = CampaignSave::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        save,
+        CampaignSave {
+            version: SAVE_VERSION,
+            level: Level::L2.id(),
+            lives: 1,
+            missions_cleared: 3,
+            stats: Stats {
+                kills: vec![(EnemyKind::Brown, 4)],
+                shots: 9,
+                score: 700,
+                ..Default::default()
+            },
+            seed: 0,
+        }
+    );
+}
+
+#[test]
+fn level_progress_keeps_best_results_and_unlocks_next_level() {
+    let mut progress = Progress::default();
+    assert!(progress.is_unlocked(Level::L1));
+    assert!(!progress.is_unlocked(Level::L2));
+
+    progress.record_win(Level::L1, 40.0, 300);
+    assert!(progress.is_unlocked(Level::L2));
+    progress.record_win(Level::L1, 30.0, 200);
+    let record = progress.record(Level::L1).unwrap();
+    assert_eq!(record.best_seconds, 30.0);
+    assert_eq!(record.best_score, 300);
+    assert!(progress.record(Level::L2).is_none());
+
+    let path = std::env::temp_dir().join(format!("tanks_progress_{}.ron", std::process::id()));
+    progress.unlocked.push(0);
+    progress.save(&path).unwrap();
+    let loaded = Progress::load(&path).unwrap();
+    assert_eq!(loaded.unlocked, vec![Level::L2.id()]);
+    assert_eq!(loaded.records, progress.records);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn wall_geometry_is_only_rebuilt_when_walls_change() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario.count_changes::<WallGeometry>();
+    scenario.step(1);
+    let changes = scenario.changes::<WallGeometry>();
+
+    let player = scenario.entities::<Player>()[0];
+    scenario.fire_at(Vec2::new(0.0, -300.0));
+    scenario.step(10);
+    assert_eq!(scenario.owned_bullets(player), 1);
+    assert_eq!(scenario.changes::<WallGeometry>(), changes);
+
+    scenario.spawn(|creator| creator.create_wall(300.0, 300.0));
+    scenario.step(1);
+    assert_eq!(scenario.changes::<WallGeometry>(), changes + 1);
+}
+
+#[test]
+fn replay_round_trips_input_and_rejects_unknown_files() {
+    let path = std::env::temp_dir().join(format!("tanks_replay_{}.rpl", std::process::id()));
+    let ticks = vec![
+        TickInput {
+            direction: Vec2::new(-1.0, 1.0),
+            cursor: Vec2::ZERO,
+            fire: false,
+        },
+        TickInput {
+            direction: Vec2::new(1.0, -1.0),
+            cursor: Vec2::new(120.5, -40.0),
+            fire: true,
+        },
+        TickInput {
+            direction: Vec2::ZERO,
+            cursor: Vec2::new(120.5, -40.0),
+            fire: true,
+        },
+        TickInput {
+            direction: Vec2::Y,
+            cursor: Vec2::new(-8.0, 300.0),
+            fire: false,
+        },
+    ];
+
+    let mut recorder = ReplayRecorder::default();
+    recorder.start_file(&path, &Level::L2, 1234).unwrap();
+    for tick in ticks.iter() {
+        recorder.record(tick).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    // A 14 byte header, a flags byte per tick and the cursor only on the two ticks it moved
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.len(), 14 + 4 + 2 * 8);
+
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!(replay.level, Level::L2);
+    assert_eq!(replay.seed, 1234);
+    assert_eq!(replay.ticks, ticks);
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    std::fs::write(&path, bad_magic).unwrap();
+    assert!(Replay::load(&path).is_err());
+
+    let mut newer = bytes;
+    newer[4] += 1;
+    std::fs::write(&path, newer).unwrap();
+    assert!(Replay::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+// helper function to list where everything in the arena is, in a fixed order
+fn sorted_positions(scenario: &mut Scenario) -> Vec<Vec2> {
+    let mut positions: Vec<Vec2> = scenario
+        .app
+        .world
+        .query::<&Position>()
+        .iter(&scenario.app.world)
+        .map(|position| position.0)
+        .collect();
+    positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    positions
+}
+
+// helper function to step a mission until it ends, returning the number of ticks it took
+fn play_until_mission_ends(
+    scenario: &mut Scenario,
+    mut input: impl FnMut(&mut Scenario, u32),
+) -> u32 {
+    let mut ticks = 0;
+    while scenario.recorded::<MissionEnded>().is_empty() {
+        assert!(ticks < 1200, "the mission did not end");
+        input(scenario, ticks);
+        scenario.step(1);
+        ticks += 1;
+    }
+    ticks
+}
+
+#[test]
+fn replayed_mission_plays_out_the_same() {
+    let directory = std::env::temp_dir().join(format!("tanks_replays_{}", std::process::id()));
+    let mut app = App::new();
+    app.insert_resource(ReplayDirectory(directory.clone()));
+    let mut recording = Scenario::with_app(app, Some(Level::L1));
+    recording.app.add_plugin(ReplayRecordingPlugin);
+    recording.record::<MissionEnded>();
+    recording.finish_briefing();
+
+    // Drive out from behind the walls, then keep shooting at the enemy tank
+    let ticks = play_until_mission_ends(&mut recording, |scenario, tick| {
+        let mut player_input = scenario
+            .app
+            .world
+            .get_resource_mut::<PlayerInput>()
+            .unwrap();
+        player_input.direction = if tick < 40 {
+            Vec2::new(-1.0, 0.0)
+        } else {
+            Vec2::ZERO
+        };
+        if tick >= 40 && tick % 30 == 10 {
+            scenario.fire_at(Vec2::new(-130.0, 150.0));
+        }
+    });
+    let ended = recording.recorded::<MissionEnded>();
+    let positions = sorted_positions(&mut recording);
+    // The replay is saved as the mission is left
+    recording.step(1);
+
+    let files: Vec<_> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1);
+    let replay = Replay::load(&files[0]).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(replay.level, Level::L1);
+    assert_eq!(replay.seed, 0);
+
+    let mut app = App::new();
+    app.insert_resource(ReplayPlayback::new(replay));
+    let mut playback = Scenario::with_app(app, Some(Level::L1));
+    playback.app.add_plugin(ReplayPlaybackPlugin);
+    playback.record::<MissionEnded>();
+    playback.finish_briefing();
+
+    assert_eq!(play_until_mission_ends(&mut playback, |_, _| {}), ticks);
+    assert_eq!(playback.recorded::<MissionEnded>()[0].won, ended[0].won);
+    assert_eq!(sorted_positions(&mut playback), positions);
+
+    // Playback stops with the recorded mission instead of going on to the next one
+    playback.step(2);
+    assert_eq!(playback.state(), GameState::MainMenu);
+}
+
+#[test]
+fn paused_mission_is_frozen_until_resumed() {
+    let mut scenario = Scenario::new();
+    scenario.idle_player();
+    scenario.idle_enemy();
+    let owner = scenario.owner();
+    let bullet = scenario.spawn_bullet(Vec2::ZERO, Vec2::new(150.0, 0.0), owner);
+    scenario.step(1);
+
+    scenario.set_state(|state| state.push(GameState::Paused));
+    scenario.step(1);
+    let paused_position = scenario.position(bullet);
+    scenario.step(60);
+    assert_eq!(scenario.state(), GameState::Paused);
+    assert_eq!(scenario.position(bullet), paused_position);
+
+    scenario.set_state(|state| state.pop());
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Playing);
+    assert!(scenario.position(bullet).x > paused_position.x);
+}
+
+#[test]
+fn restarting_paused_mission_sets_level_up_again() {
+    let mut scenario = Scenario::with_level(Some(Level::L1));
+    scenario.fire_at(Vec2::new(0.0, 100.0));
+    scenario.step(1);
+    let player = scenario.entities::<Player>()[0];
+    assert!(scenario.owned_bullets(player) > 0);
+
+    scenario.set_state(|state| state.push(GameState::Paused));
+    scenario.step(1);
+    scenario.set_state(|state| state.replace(GameState::Restarting));
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Briefing);
+    assert!(!scenario.exists(player));
+    assert_eq!(scenario.count::<Player>(), 1);
+    assert_eq!(scenario.count::<BrownTank>(), 1);
+    assert_eq!(scenario.owned_bullets(player), 0);
+}
+
+#[test]
+fn finishing_campaign_returns_to_main_menu() {
+    let mut scenario = Scenario::with_level(Some(Level::L2));
+    scenario.step(1);
+    let enemies = scenario.entities::<Enemy>();
+    for enemy in enemies {
+        scenario.app.world.despawn(enemy);
+    }
+
+    scenario.step(2);
+    assert_eq!(scenario.state(), GameState::Win);
+
+    scenario.step(TIMER_TICKS);
+    assert_eq!(scenario.state(), GameState::CampaignComplete);
+    assert_eq!(
+        scenario.app.world.get_resource::<CurrentLevel>().unwrap().0,
+        None
+    );
+
+    scenario.step(361);
+    assert_eq!(scenario.state(), GameState::MainMenu);
+    assert_eq!(scenario.count::<Player>(), 0);
+}
+
+#[test]
+fn env_reset_observes_level() {
+    let mut env = Env::new();
+    let observation = env.reset(7, Level::L2);
+    assert_eq!(observation.player, Some(Vec2::ZERO));
+    assert_eq!(observation.enemies.len(), 2);
+    assert_eq!(observation.walls.len(), 5);
+    assert!(observation
+        .bullets
+        .iter()
+        .all(|bullet| !bullet.player_owned));
+}
+
+#[test]
+fn env_step_fires_player_bullet() {
+    let mut env = Env::new();
+    env.reset(0, Level::L1);
+    let (observation, reward, done) = env.step(Action {
+        direction: Vec2::ZERO,
+        aim: Vec2::new(100.0, 0.0),
+        fire: true,
+    });
+    assert_eq!(reward, 0.0);
+    assert!(!done);
+    assert!(observation
+        .bullets
+        .iter()
+        .any(|bullet| bullet.player_owned && bullet.velocity.x > 0.0 && bullet.velocity.y == 0.0));
+}
+
+#[test]
+fn env_episode_ends_with_reward() {
+    let mut env = Env::new();
+    env.reset(0, Level::L1);
+    // Drive into the open and keep firing at the enemy until one of the tanks is destroyed
+    let mut total = 0.0;
+    let mut done = false;
+    for tick in 0..3600 {
+        let (_, reward, finished) = env.step(Action {
+            direction: if tick < 60 { -Vec2::X } else { Vec2::ZERO },
+            aim: Vec2::new(-130.0, 150.0),
+            fire: tick % 30 == 0,
+        });
+        total += reward;
+        // The episode ends on the same step the tank is destroyed
+        assert_eq!(finished, reward != 0.0);
+        if finished {
+            done = true;
+            break;
+        }
+    }
+
+    assert!(done);
+    assert!(total == 1.0 || total == -1.0);
+    let (_, reward, done) = env.step(Action::default());
+    assert_eq!(reward, 0.0);
+    assert!(done);
+}
+
+#[test]
+fn env_is_deterministic() {
+    let run = || {
+        let mut env = Env::new();
+        let mut observations = vec![env.reset(3, Level::L2)];
+        for tick in 0..600 {
+            let (observation, _, _) = env.step(Action {
+                direction: Vec2::new((tick / 100 % 3) as f32 - 1.0, 0.0),
+                aim: Vec2::new(100.0, 150.0),
+                fire: tick % 45 == 0,
+            });
+            observations.push(observation);
+        }
+        observations
+    };
+
+    assert_eq!(run(), run());
+}
+
+// helper function to parse the given command line arguments
+fn parse_options(args: &[&str]) -> Result<Options, String> {
+    Options::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn options_are_parsed_from_arguments() {
+    assert_eq!(parse_options(&[]), Ok(Options::default()));
+    assert_eq!(
+        parse_options(&[
+            "--level",
+            "2",
+            "--seed",
+            "7",
+            "--windowed",
+            "800x600",
+            "--replay",
+            "run.rpl",
+            "--headless",
+            "--ticks",
+            "100",
+            "--no-fps",
+        ]),
+        Ok(Options {
+            level: Some(Level::L2),
+            seed: 7,
+            window_size: Some((800.0, 600.0)),
+            replay: Some("run.rpl".into()),
+            headless: true,
+            ticks: Some(100),
+            show_fps: false,
+        })
+    );
+}
+
+#[test]
+fn bad_options_are_rejected() {
+    for args in [
+        &["--level", "9"][..],
+        &["--level", "first"],
+        &["--seed", "-1"],
+        &["--windowed", "0x600"],
+        &["--windowed", "800"],
+        &["--windowed", "infxinf"],
+        &["--windowed", "1e30x1e30"],
+        &["--windowed", "800.5x600"],
+        &["--ticks", "many"],
+        &["--seed"],
+        &["--replay"],
+        &["--fast"],
+    ] {
+        assert!(parse_options(args).is_err(), "{:?} was accepted", args);
+    }
+}
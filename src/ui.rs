@@ -0,0 +1,492 @@
+// Everything needed to show the game in a window: cameras, sprites drawn between simulation ticks
+// and the text overlay.
+use crate::{
+    ai::{BrownTank, Enemy},
+    combat::{arena_units_per_pixel, BulletOwner, Weapon},
+    config::GameConfig,
+    flow::{Campaign, GameState},
+    highscore::{HighScores, PendingHighScore},
+    level::{CurrentLevel, Texture},
+    player::Player,
+    simulation::{Position, PreviousPosition, SimulationClock, TICK_SECONDS},
+    stats::{CampaignStats, MissionStats, Stats},
+};
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    render::view::Visibility,
+    transform::TransformSystem,
+    ui::FocusPolicy,
+};
+
+#[derive(Component)]
+pub struct UiElement;
+#[derive(Component)]
+struct GameCamera;
+#[derive(Component)]
+struct FpsText;
+#[derive(Component)]
+struct BannerText;
+#[derive(Component)]
+struct ResultsText;
+#[derive(Component)]
+struct HudText;
+
+// Whether the frame rate is shown, starting out as the config says unless set before the game
+// starts. The settings menu toggles it.
+pub struct ShowFps(pub bool);
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_startup_system(show_fps_setup_system)
+            .add_startup_system(setup_cameras)
+            .add_startup_system(setup_text)
+            .add_system(sprite_setup_system)
+            .add_system(camera_scale_system)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolation_system.before(TransformSystem::TransformPropagate),
+            )
+            .add_system(text_update_system)
+            .add_system(banner_text_system)
+            .add_system(results_text_system)
+            .add_system(hud_system.chain(hud_text_system));
+    }
+}
+
+// Camera system
+fn setup_cameras(mut commands: Commands) {
+    // game camera
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(GameCamera);
+
+    // UI camera needed to render text
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+// Text systems
+fn setup_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        // Let the mouse through to the menus below the text
+        .insert(FocusPolicy::Pass)
+        .insert(UiElement)
+        .with_children(|parent| {
+            // FPS text
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(15.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: "FPS: ".to_string(),
+                                style: TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 60.0,
+                                    color: Color::NONE,
+                                },
+                            },
+                            TextSection {
+                                value: "".to_string(),
+                                style: TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 60.0,
+                                    color: Color::NONE,
+                                },
+                            },
+                        ],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(UiElement)
+                .insert(FpsText);
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(70.0), Val::Percent(100.0)),
+                        // List the banner above the results
+                        flex_direction: FlexDirection::ColumnReverse,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass)
+                .insert(UiElement)
+                .with_children(|p2| {
+                    // Banner text shown between missions
+                    p2.spawn_bundle(TextBundle {
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: "".to_string(),
+                                style: TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 60.0,
+                                    color: Color::NONE,
+                                },
+                            }],
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(BannerText)
+                    .insert(UiElement);
+
+                    // Results text shown below the banner
+                    p2.spawn_bundle(TextBundle {
+                        text: Text {
+                            sections: vec![TextSection {
+                                value: "".to_string(),
+                                style: TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 30.0,
+                                    color: Color::NONE,
+                                },
+                            }],
+                            alignment: TextAlignment {
+                                horizontal: HorizontalAlign::Center,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    })
+                    .insert(ResultsText)
+                    .insert(UiElement);
+                });
+
+            // HUD text, with the mission details followed by the bullets available and those in
+            // the air
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(15.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    text: Text {
+                        sections: (0..3)
+                            .map(|_| TextSection {
+                                value: "".to_string(),
+                                style: TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 40.0,
+                                    color: Color::NONE,
+                                },
+                            })
+                            .collect(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(UiElement)
+                .insert(HudText);
+        });
+}
+
+fn camera_scale_system(
+    windows: Res<Windows>,
+    mut query: Query<&mut OrthographicProjection, With<GameCamera>>,
+) {
+    // Zoom the game camera so the whole arena fits in the window, whatever its size
+    // Minimized windows have no size to fit the arena in
+    if let Some(window) = windows
+        .get_primary()
+        .filter(|window| window.width() > 0.0 && window.height() > 0.0)
+    {
+        let scale = arena_units_per_pixel(window.width(), window.height());
+        for mut projection in query.iter_mut() {
+            // Only touch the projection when the scale changes, since that rebuilds the camera
+            if projection.scale != scale {
+                projection.scale = scale;
+            }
+        }
+    }
+}
+
+fn show_fps_setup_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    show_fps: Option<Res<ShowFps>>,
+) {
+    if show_fps.is_none() {
+        commands.insert_resource(ShowFps(config.show_fps));
+    }
+}
+
+fn text_update_system(
+    diagnostics: Res<Diagnostics>,
+    show_fps: Res<ShowFps>,
+    mut query: Query<&mut Text, With<FpsText>>,
+) {
+    for mut text in query.iter_mut() {
+        // Hide the frame rate by setting its color to NONE, so it can be toggled from the menu
+        let color = if show_fps.0 {
+            Color::WHITE
+        } else {
+            Color::NONE
+        };
+        for section in text.sections.iter_mut() {
+            section.style.color = color;
+        }
+
+        if let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
+            if let Some(average) = fps.average() {
+                text.sections[1].value = format!("{:.2}", average);
+            }
+        }
+    }
+}
+
+fn banner_text_system(
+    game_state: Res<State<GameState>>,
+    campaign: Res<Campaign>,
+    current_level: Res<CurrentLevel>,
+    enemy_query: Query<&Enemy>,
+    brown_tank_query: Query<&BrownTank>,
+    mut query: Query<&mut Text, With<BannerText>>,
+) {
+    // Show the mission briefing and how the mission or campaign ended, and hide the banner by
+    // setting its color to NONE otherwise
+    if let Ok(mut text) = query.get_single_mut() {
+        let banner = match game_state.current() {
+            GameState::Briefing => current_level.0.map(|level| {
+                format!(
+                    "Mission {} \u{2014} {}\nEnemy tanks: {}\nBrown tanks: {}",
+                    level.number(),
+                    level.name(),
+                    enemy_query.iter().count(),
+                    brown_tank_query.iter().count()
+                )
+            }),
+            GameState::Win => Some("Mission complete!".to_string()),
+            GameState::CampaignComplete => Some("Campaign complete!".to_string()),
+            GameState::Lose => Some(format!("Lives remaining: {}", campaign.lives)),
+            GameState::GameOver => Some(format!(
+                "Game Over\nMissions cleared: {}\nReached mission {}",
+                campaign.missions_cleared,
+                current_level.0.map_or(0, |level| level.number())
+            )),
+            _ => None,
+        };
+        match banner {
+            Some(banner) => {
+                text.sections[0].value = banner;
+                text.sections[0].style.color = Color::WHITE;
+            }
+            None => text.sections[0].style.color = Color::NONE,
+        }
+    }
+}
+
+fn results_text_system(
+    game_state: Res<State<GameState>>,
+    mission_stats: Res<MissionStats>,
+    campaign_stats: Res<CampaignStats>,
+    high_scores: Option<Res<HighScores>>,
+    pending: Option<Res<PendingHighScore>>,
+    mut query: Query<&mut Text, With<ResultsText>>,
+) {
+    // Show the results of the mission once it is won, and of the whole campaign along with the
+    // high scores once it is over
+    if let Ok(mut text) = query.get_single_mut() {
+        let results = match game_state.current() {
+            GameState::Win => {
+                let mut results = stats_text(&mission_stats.stats);
+                if let Some(multiplier) = mission_stats.time_multiplier {
+                    results.push_str(&format!("\nTime bonus: x{:.2}", multiplier));
+                }
+                Some(results)
+            }
+            GameState::GameOver | GameState::CampaignComplete => {
+                let mut results = stats_text(&campaign_stats.0);
+                if pending.is_some_and(|pending| pending.0.is_some()) {
+                    results.push_str("\n\nNew high score!");
+                }
+                if let Some(high_scores) = high_scores {
+                    results.push_str("\n\nHigh scores");
+                    for (index, entry) in high_scores.entries.iter().enumerate() {
+                        results.push_str(&format!(
+                            "\n{}. {}  {}",
+                            index + 1,
+                            entry.name,
+                            entry.score
+                        ));
+                    }
+                }
+                Some(results)
+            }
+            _ => None,
+        };
+        match results {
+            Some(results) => {
+                text.sections[0].value = results;
+                text.sections[0].style.color = Color::WHITE;
+            }
+            None => text.sections[0].style.color = Color::NONE,
+        }
+    }
+}
+
+// helper function to list stats one per line
+fn stats_text(stats: &Stats) -> String {
+    let mut lines = vec![];
+    for (kind, count) in stats.kills.iter() {
+        lines.push(format!("{}s destroyed: {}", kind.name(), count));
+    }
+    lines.push(format!("Shots fired: {}", stats.shots));
+    lines.push(match stats.accuracy() {
+        Some(accuracy) => format!("Accuracy: {:.0}%", accuracy * 100.0),
+        None => "Accuracy: -".to_string(),
+    });
+    lines.push(format!("Ricochet kills: {}", stats.ricochet_kills));
+    lines.push(format!("Bullets cancelled: {}", stats.cancels));
+    lines.push(format!("Time: {:.1}s", stats.seconds()));
+    lines.push(format!("Score: {}", stats.score));
+    lines.join("\n")
+}
+
+type HudQueries<'w, 's> = (
+    Query<'w, 's, &'static Weapon, With<Player>>,
+    Query<'w, 's, &'static BulletOwner>,
+    Query<'w, 's, &'static Enemy>,
+);
+
+// Values shown in the HUD while a mission is in progress
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hud {
+    pub mission: u32,
+    pub enemies: usize,
+    pub lives: u32,
+    // Campaign score so far, including the mission in progress
+    pub score: u32,
+    // Shots the player's weapon can still fire, and those of its bullets in the air
+    pub bullets_available: usize,
+    pub bullets_in_flight: usize,
+}
+
+pub fn hud_system(
+    campaign: Res<Campaign>,
+    current_level: Res<CurrentLevel>,
+    mission_stats: Res<MissionStats>,
+    campaign_stats: Res<CampaignStats>,
+    (player_query, bullet_query, enemy_query): HudQueries,
+) -> Hud {
+    // One pip per shot the player's weapon can have in the air, dimmed while it is in flight
+    let (bullets_available, bullets_in_flight) = match player_query.get_single() {
+        Ok(weapon) => {
+            let in_flight = bullet_query
+                .iter()
+                .filter(|owner| player_query.get(owner.0).is_ok())
+                .count();
+            let capacity = weapon.capacity as usize;
+            (capacity.saturating_sub(in_flight), in_flight.min(capacity))
+        }
+        Err(_) => (0, 0),
+    };
+
+    Hud {
+        mission: current_level.0.map_or(0, |level| level.number()),
+        enemies: enemy_query.iter().count(),
+        lives: campaign.lives,
+        score: campaign_stats.0.score + mission_stats.stats.score,
+        bullets_available,
+        bullets_in_flight,
+    }
+}
+
+fn hud_text_system(
+    In(hud): In<Hud>,
+    game_state: Res<State<GameState>>,
+    campaign_stats: Res<CampaignStats>,
+    mut query: Query<&mut Text, With<HudText>>,
+) {
+    // Show the mission in progress and the campaign score outside of the menus. Missions are only
+    // added to the campaign once they end, so only the score is left once they do.
+    if let Ok(mut text) = query.get_single_mut() {
+        let in_mission = match game_state.current() {
+            GameState::Briefing | GameState::Playing | GameState::Paused => true,
+            GameState::Win
+            | GameState::Lose
+            | GameState::GameOver
+            | GameState::CampaignComplete => false,
+            GameState::MainMenu | GameState::Restarting => {
+                for section in text.sections.iter_mut() {
+                    section.style.color = Color::NONE;
+                }
+                return;
+            }
+        };
+
+        if !in_mission {
+            text.sections[0].value = format!("Score: {}", campaign_stats.0.score);
+            text.sections[0].style.color = Color::WHITE;
+            for section in text.sections[1..].iter_mut() {
+                section.style.color = Color::NONE;
+            }
+            return;
+        }
+
+        text.sections[0].value = format!(
+            "Mission {}\nEnemies: {}\nLives: {}\nScore: {}\nBullets: ",
+            hud.mission, hud.enemies, hud.lives, hud.score
+        );
+        text.sections[0].style.color = Color::WHITE;
+        text.sections[1].value = "\u{2022}".repeat(hud.bullets_available);
+        text.sections[1].style.color = Color::WHITE;
+        text.sections[2].value = "\u{2022}".repeat(hud.bullets_in_flight);
+        text.sections[2].style.color = Color::DARK_GRAY;
+    }
+}
+
+fn interpolation_system(
+    clock: Res<SimulationClock>,
+    game_state: Res<State<GameState>>,
+    mut query: Query<(&Position, &PreviousPosition, &mut Transform)>,
+) {
+    // Render entities part of the way between their last two simulated positions, based on how
+    // far the clock is into the next tick. Nothing moves outside of missions in progress, so
+    // render entities where they stopped then.
+    let alpha = if *game_state.current() == GameState::Playing {
+        (clock.accumulator / TICK_SECONDS).min(1.0) as f32
+    } else {
+        1.0
+    };
+    for (position, previous_position, mut transform) in query.iter_mut() {
+        let translation = previous_position.0.lerp(position.0, alpha);
+        transform.translation = translation.extend(transform.translation.z);
+    }
+}
+
+// Rendering systems
+fn sprite_setup_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &Texture, &Position), Added<Texture>>,
+) {
+    // Give newly spawned entities a sprite, starting out at their simulated position
+    for (entity, texture, position) in query.iter() {
+        commands.entity(entity).insert_bundle(SpriteBundle {
+            texture: asset_server.load(texture.0),
+            transform: Transform::from_translation(position.0.extend(0.0)),
+            ..Default::default()
+        });
+    }
+}